@@ -0,0 +1,11 @@
+-- Reviews left on a listing
+CREATE TABLE IF NOT EXISTS comments (
+    id BIGSERIAL PRIMARY KEY,
+    author TEXT NOT NULL,
+    content TEXT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    rating DOUBLE PRECISION NOT NULL,
+    listing_id BIGINT NOT NULL REFERENCES listings (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS comments_listing_id_idx ON comments (listing_id);
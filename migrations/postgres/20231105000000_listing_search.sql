@@ -0,0 +1,9 @@
+-- Full-text search over listings. Company and position weigh the most, then school, then description.
+ALTER TABLE listings ADD COLUMN IF NOT EXISTS search TSVECTOR GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', company), 'A') ||
+    setweight(to_tsvector('english', position), 'A') ||
+    setweight(to_tsvector('english', school), 'B') ||
+    setweight(to_tsvector('english', description), 'C')
+) STORED;
+
+CREATE INDEX IF NOT EXISTS listings_search_idx ON listings USING GIN (search);
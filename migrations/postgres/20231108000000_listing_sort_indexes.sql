@@ -0,0 +1,2 @@
+-- Supports browsing listings alphabetically by company
+CREATE INDEX IF NOT EXISTS listings_company_id_idx ON listings (company, id);
@@ -0,0 +1,16 @@
+-- Structured details for listings. Existing listings leave them empty.
+CREATE TYPE work_mode AS ENUM ('remote', 'hybrid', 'onsite');
+CREATE TYPE season AS ENUM ('spring', 'summer', 'fall', 'winter');
+
+ALTER TABLE listings
+    ADD COLUMN location TEXT NOT NULL DEFAULT '',
+    ADD COLUMN work_mode work_mode,
+    ADD COLUMN paid BOOLEAN,
+    ADD COLUMN hourly_rate DOUBLE PRECISION CHECK (hourly_rate > 0),
+    ADD COLUMN season season,
+    ADD COLUMN year INTEGER,
+    -- Milliseconds since the epoch
+    ADD COLUMN deadline BIGINT;
+
+CREATE INDEX IF NOT EXISTS listings_work_mode_idx ON listings (work_mode);
+CREATE INDEX IF NOT EXISTS listings_season_year_idx ON listings (season, year);
@@ -0,0 +1,33 @@
+-- Companies with one canonical name, so ratings can be combined across all of a company's listings
+CREATE TABLE IF NOT EXISTS companies (
+    id BIGSERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    -- Website domain, like example.com
+    domain TEXT,
+    logo_url TEXT
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS companies_name_idx ON companies (LOWER(name));
+
+-- Other names a company goes by, stored lowercase
+CREATE TABLE IF NOT EXISTS company_aliases (
+    alias TEXT PRIMARY KEY,
+    company_id BIGINT NOT NULL REFERENCES companies (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS company_aliases_company_id_idx ON company_aliases (company_id);
+
+-- Each differently cased company name becomes one company. Blank ones become "Unknown".
+INSERT INTO companies (name)
+    SELECT MIN(COALESCE(NULLIF(TRIM(company), ''), 'Unknown')) FROM listings
+    GROUP BY LOWER(COALESCE(NULLIF(TRIM(company), ''), 'Unknown'))
+ON CONFLICT DO NOTHING;
+
+-- Listings keep the canonical name in company for searching and sorting, company_id is what they are grouped by
+ALTER TABLE listings ADD COLUMN IF NOT EXISTS company_id BIGINT REFERENCES companies (id);
+UPDATE listings l SET company_id = c.id
+    FROM companies c WHERE LOWER(c.name) = LOWER(COALESCE(NULLIF(TRIM(l.company), ''), 'Unknown'));
+UPDATE listings l SET company = c.name FROM companies c WHERE c.id = l.company_id;
+ALTER TABLE listings ALTER COLUMN company_id SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS listings_company_ref_idx ON listings (company_id);
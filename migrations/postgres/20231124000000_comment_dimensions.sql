@@ -0,0 +1,7 @@
+-- Optional ratings for each part of the internship, from 0 to 1 like rating
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS mentorship DOUBLE PRECISION CHECK (mentorship BETWEEN 0 AND 1);
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS compensation DOUBLE PRECISION CHECK (compensation BETWEEN 0 AND 1);
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS work_life_balance DOUBLE PRECISION CHECK (work_life_balance BETWEEN 0 AND 1);
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS learning DOUBLE PRECISION CHECK (learning BETWEEN 0 AND 1);
+-- How likely a return offer is
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS return_offer DOUBLE PRECISION CHECK (return_offer BETWEEN 0 AND 1);
@@ -0,0 +1,22 @@
+-- Ratings stored on each listing and company, so previews don't have to load every comment.
+-- The application recomputes them from the comments whenever one is added, and for every listing after migrating.
+ALTER TABLE listings ADD COLUMN IF NOT EXISTS rating_average DOUBLE PRECISION NOT NULL DEFAULT 0;
+ALTER TABLE listings ADD COLUMN IF NOT EXISTS rating_count BIGINT NOT NULL DEFAULT 0;
+-- Number of comments with 1 to 5 stars
+ALTER TABLE listings ADD COLUMN IF NOT EXISTS stars_1 BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE listings ADD COLUMN IF NOT EXISTS stars_2 BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE listings ADD COLUMN IF NOT EXISTS stars_3 BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE listings ADD COLUMN IF NOT EXISTS stars_4 BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE listings ADD COLUMN IF NOT EXISTS stars_5 BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE companies ADD COLUMN IF NOT EXISTS rating_average DOUBLE PRECISION NOT NULL DEFAULT 0;
+ALTER TABLE companies ADD COLUMN IF NOT EXISTS rating_count BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE companies ADD COLUMN IF NOT EXISTS stars_1 BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE companies ADD COLUMN IF NOT EXISTS stars_2 BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE companies ADD COLUMN IF NOT EXISTS stars_3 BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE companies ADD COLUMN IF NOT EXISTS stars_4 BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE companies ADD COLUMN IF NOT EXISTS stars_5 BIGINT NOT NULL DEFAULT 0;
+
+-- Supports sorting by the stored ratings
+CREATE INDEX IF NOT EXISTS listings_rating_average_id_idx ON listings (rating_average, id);
+CREATE INDEX IF NOT EXISTS listings_rating_count_id_idx ON listings (rating_count, id);
@@ -0,0 +1,21 @@
+-- Structured interview experiences shared for a listing
+CREATE TYPE interview_outcome AS ENUM ('offer', 'rejected', 'withdrew', 'pending');
+
+CREATE TABLE IF NOT EXISTS interview_reports (
+    id BIGSERIAL PRIMARY KEY,
+    listing_id BIGINT NOT NULL REFERENCES listings (id) ON DELETE CASCADE,
+    author TEXT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    rounds INTEGER NOT NULL CHECK (rounds BETWEEN 1 AND 20),
+    -- Which kinds of rounds the process had
+    online_assessment BOOLEAN NOT NULL DEFAULT FALSE,
+    phone_screen BOOLEAN NOT NULL DEFAULT FALSE,
+    onsite BOOLEAN NOT NULL DEFAULT FALSE,
+    questions TEXT NOT NULL DEFAULT '',
+    difficulty INTEGER NOT NULL CHECK (difficulty BETWEEN 1 AND 5),
+    -- Days from applying to hearing the outcome
+    process_days INTEGER CHECK (process_days >= 0),
+    outcome interview_outcome NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS interview_reports_listing_id_idx ON interview_reports (listing_id);
@@ -0,0 +1,20 @@
+-- What people were paid for an internship. Only shown summed up over several reports.
+CREATE TYPE pay_period AS ENUM ('hourly', 'monthly');
+
+CREATE TABLE IF NOT EXISTS compensation_reports (
+    id BIGSERIAL PRIMARY KEY,
+    listing_id BIGINT NOT NULL REFERENCES listings (id) ON DELETE CASCADE,
+    author TEXT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    pay DOUBLE PRECISION NOT NULL CHECK (pay > 0),
+    pay_period pay_period NOT NULL,
+    -- Dollars per month
+    housing_stipend DOUBLE PRECISION CHECK (housing_stipend >= 0),
+    -- One-time amounts in dollars
+    relocation DOUBLE PRECISION CHECK (relocation >= 0),
+    signing_bonus DOUBLE PRECISION CHECK (signing_bonus >= 0),
+    year INTEGER NOT NULL,
+    location TEXT NOT NULL DEFAULT ''
+);
+
+CREATE INDEX IF NOT EXISTS compensation_reports_listing_id_idx ON compensation_reports (listing_id);
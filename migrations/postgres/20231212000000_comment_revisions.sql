@@ -0,0 +1,18 @@
+-- When the comment was last edited, NULL if it never was
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS edited_at BIGINT;
+
+-- Earlier versions of edited comments. timestamp is when that version was posted or last edited.
+CREATE TABLE IF NOT EXISTS comment_revisions (
+    id BIGSERIAL PRIMARY KEY,
+    comment_id BIGINT NOT NULL REFERENCES comments (id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    rating DOUBLE PRECISION NOT NULL,
+    mentorship DOUBLE PRECISION,
+    compensation DOUBLE PRECISION,
+    work_life_balance DOUBLE PRECISION,
+    learning DOUBLE PRECISION,
+    return_offer DOUBLE PRECISION,
+    timestamp BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS comment_revisions_comment_id_idx ON comment_revisions (comment_id);
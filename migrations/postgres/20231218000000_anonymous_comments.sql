@@ -0,0 +1,11 @@
+-- Anonymous comments are shown under a pseudonym instead of the author's name. author and user_id still say who posted them.
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS anonymous BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- The number each user posts anonymously under on a listing, given out in order, so their comments there can be told apart
+CREATE TABLE IF NOT EXISTS listing_pseudonyms (
+    listing_id BIGINT NOT NULL REFERENCES listings (id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    number BIGINT NOT NULL,
+    PRIMARY KEY (listing_id, user_id),
+    UNIQUE (listing_id, number)
+);
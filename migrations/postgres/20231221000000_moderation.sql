@@ -0,0 +1,42 @@
+-- Hidden comments and listings stay in the database for moderators but aren't shown or counted in ratings
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS hidden BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE listings ADD COLUMN IF NOT EXISTS hidden BOOLEAN NOT NULL DEFAULT FALSE;
+-- The account that posted the listing. Listings posted before this was recorded have none.
+ALTER TABLE listings ADD COLUMN IF NOT EXISTS user_id INTEGER REFERENCES users (id) ON DELETE SET NULL;
+-- Banned users can't log in
+ALTER TABLE users ADD COLUMN IF NOT EXISTS banned BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TYPE report_reason AS ENUM ('spam', 'harassment', 'fake', 'offensive', 'other');
+CREATE TYPE moderation_action AS ENUM ('dismiss', 'hide', 'delete', 'warn', 'ban');
+
+-- Users flagging a comment or a listing, at most once each
+CREATE TABLE IF NOT EXISTS reports (
+    id BIGSERIAL PRIMARY KEY,
+    reporter_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    comment_id BIGINT REFERENCES comments (id) ON DELETE CASCADE,
+    listing_id BIGINT REFERENCES listings (id) ON DELETE CASCADE,
+    reason report_reason NOT NULL,
+    details TEXT NOT NULL DEFAULT '',
+    timestamp BIGINT NOT NULL,
+    -- When a moderator dealt with it, NULL while it is open
+    resolved_at BIGINT,
+    CHECK ((comment_id IS NULL) <> (listing_id IS NULL)),
+    UNIQUE (reporter_id, comment_id),
+    UNIQUE (reporter_id, listing_id)
+);
+
+CREATE INDEX IF NOT EXISTS reports_open_idx ON reports (timestamp) WHERE resolved_at IS NULL;
+
+-- Every action a moderator takes. The ids aren't foreign keys so entries outlive what they point at.
+CREATE TABLE IF NOT EXISTS moderation_log (
+    id BIGSERIAL PRIMARY KEY,
+    moderator_id INTEGER NOT NULL,
+    action moderation_action NOT NULL,
+    report_id BIGINT,
+    comment_id BIGINT,
+    listing_id BIGINT,
+    -- The author of the reported content
+    user_id INTEGER,
+    note TEXT NOT NULL DEFAULT '',
+    timestamp BIGINT NOT NULL
+);
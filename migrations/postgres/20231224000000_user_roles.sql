@@ -0,0 +1,6 @@
+-- From least to most trusted. School admins manage the moderators of their own school.
+CREATE TYPE user_role AS ENUM ('user', 'moderator', 'school_admin', 'site_admin');
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS role user_role NOT NULL DEFAULT 'user';
+
+CREATE INDEX IF NOT EXISTS users_staff_idx ON users (school_id) WHERE role <> 'user';
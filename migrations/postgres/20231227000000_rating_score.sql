@@ -0,0 +1,6 @@
+-- The Bayesian score from rating::RatingSummary, stored so listings can be sorted by it.
+-- It uses PRIOR_RATING and PRIOR_REVIEWS, so it's filled in by the application after migrating and whenever a listing is added.
+ALTER TABLE listings ADD COLUMN IF NOT EXISTS rating_score DOUBLE PRECISION NOT NULL DEFAULT 0;
+
+-- Supports sorting by the score
+CREATE INDEX IF NOT EXISTS listings_rating_score_id_idx ON listings (rating_score, id);
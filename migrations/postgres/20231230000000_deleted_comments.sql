@@ -0,0 +1,5 @@
+-- Deleted comments that others had replied to. The row is kept, blanked, so the replies stay threaded,
+-- but it isn't counted in ratings.
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS deleted BOOLEAN NOT NULL DEFAULT FALSE;
+-- Who deleted it. Only a comment its own author deleted can be posted over again, so a moderator's deletion stays.
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS deleted_by INTEGER REFERENCES users (id) ON DELETE SET NULL;
@@ -0,0 +1,7 @@
+-- Accounts created through the registration popup
+CREATE TABLE IF NOT EXISTS users (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    password TEXT NOT NULL,
+    school TEXT NOT NULL
+);
@@ -0,0 +1,11 @@
+-- Internship listings shown on the home page
+CREATE TABLE IF NOT EXISTS listings (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    company TEXT NOT NULL,
+    position TEXT NOT NULL,
+    description TEXT NOT NULL,
+    url TEXT NOT NULL,
+    school TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS listings_school_idx ON listings (school);
@@ -0,0 +1,11 @@
+-- Reviews left on a listing
+CREATE TABLE IF NOT EXISTS comments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    author TEXT NOT NULL,
+    content TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    rating REAL NOT NULL,
+    listing_id INTEGER NOT NULL REFERENCES listings (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS comments_listing_id_idx ON comments (listing_id);
@@ -0,0 +1,25 @@
+-- Full-text search over listings, kept in sync with the listings table by triggers
+CREATE VIRTUAL TABLE IF NOT EXISTS listings_search USING fts5(
+    company, position, description, school,
+    content = 'listings', content_rowid = 'id', tokenize = 'porter unicode61'
+);
+
+INSERT INTO listings_search (rowid, company, position, description, school)
+    SELECT id, company, position, description, school FROM listings;
+
+CREATE TRIGGER IF NOT EXISTS listings_search_insert AFTER INSERT ON listings BEGIN
+    INSERT INTO listings_search (rowid, company, position, description, school)
+        VALUES (new.id, new.company, new.position, new.description, new.school);
+END;
+
+CREATE TRIGGER IF NOT EXISTS listings_search_delete AFTER DELETE ON listings BEGIN
+    INSERT INTO listings_search (listings_search, rowid, company, position, description, school)
+        VALUES ('delete', old.id, old.company, old.position, old.description, old.school);
+END;
+
+CREATE TRIGGER IF NOT EXISTS listings_search_update AFTER UPDATE ON listings BEGIN
+    INSERT INTO listings_search (listings_search, rowid, company, position, description, school)
+        VALUES ('delete', old.id, old.company, old.position, old.description, old.school);
+    INSERT INTO listings_search (rowid, company, position, description, school)
+        VALUES (new.id, new.company, new.position, new.description, new.school);
+END;
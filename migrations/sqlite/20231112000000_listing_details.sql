@@ -0,0 +1,12 @@
+-- Structured details for listings. Existing listings leave them empty.
+ALTER TABLE listings ADD COLUMN location TEXT NOT NULL DEFAULT '';
+ALTER TABLE listings ADD COLUMN work_mode TEXT CHECK (work_mode IN ('remote', 'hybrid', 'onsite'));
+ALTER TABLE listings ADD COLUMN paid BOOLEAN;
+ALTER TABLE listings ADD COLUMN hourly_rate REAL CHECK (hourly_rate > 0);
+ALTER TABLE listings ADD COLUMN season TEXT CHECK (season IN ('spring', 'summer', 'fall', 'winter'));
+ALTER TABLE listings ADD COLUMN year INTEGER;
+-- Milliseconds since the epoch
+ALTER TABLE listings ADD COLUMN deadline INTEGER;
+
+CREATE INDEX IF NOT EXISTS listings_work_mode_idx ON listings (work_mode);
+CREATE INDEX IF NOT EXISTS listings_season_year_idx ON listings (season, year);
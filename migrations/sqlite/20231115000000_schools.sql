@@ -0,0 +1,92 @@
+-- Schools with one canonical name, so "UCLA" and "University of California, Los Angeles" are the same school
+CREATE TABLE IF NOT EXISTS schools (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS schools_name_idx ON schools (LOWER(name));
+
+-- Other names a school goes by, stored lowercase
+CREATE TABLE IF NOT EXISTS school_aliases (
+    alias TEXT PRIMARY KEY,
+    school_id INTEGER NOT NULL REFERENCES schools (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS school_aliases_school_id_idx ON school_aliases (school_id);
+
+-- Email domains handed out by a school, stored lowercase
+CREATE TABLE IF NOT EXISTS school_domains (
+    domain TEXT PRIMARY KEY,
+    school_id INTEGER NOT NULL REFERENCES schools (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS school_domains_school_id_idx ON school_domains (school_id);
+
+INSERT OR IGNORE INTO schools (name) VALUES
+    ('University of California, Los Angeles'),
+    ('University of California, Berkeley'),
+    ('University of California, San Diego'),
+    ('Stanford University'),
+    ('University of Southern California'),
+    ('Massachusetts Institute of Technology');
+
+WITH seed (alias, name) AS (VALUES
+    ('ucla', 'University of California, Los Angeles'),
+    ('uc berkeley', 'University of California, Berkeley'),
+    ('berkeley', 'University of California, Berkeley'),
+    ('cal', 'University of California, Berkeley'),
+    ('ucsd', 'University of California, San Diego'),
+    ('uc san diego', 'University of California, San Diego'),
+    ('stanford', 'Stanford University'),
+    ('usc', 'University of Southern California'),
+    ('mit', 'Massachusetts Institute of Technology')
+)
+INSERT OR IGNORE INTO school_aliases (alias, school_id)
+    SELECT seed.alias, s.id FROM seed JOIN schools s ON s.name = seed.name;
+
+WITH seed (domain, name) AS (VALUES
+    ('ucla.edu', 'University of California, Los Angeles'),
+    ('berkeley.edu', 'University of California, Berkeley'),
+    ('ucsd.edu', 'University of California, San Diego'),
+    ('stanford.edu', 'Stanford University'),
+    ('usc.edu', 'University of Southern California'),
+    ('mit.edu', 'Massachusetts Institute of Technology')
+)
+INSERT OR IGNORE INTO school_domains (domain, school_id)
+    SELECT seed.domain, s.id FROM seed JOIN schools s ON s.name = seed.name;
+
+-- Free-text schools that don't match a known name or alias become schools of their own. Blank ones become "Unknown".
+CREATE TEMPORARY TABLE existing_schools AS
+    SELECT DISTINCT COALESCE(NULLIF(TRIM(school), ''), 'Unknown') AS school
+    FROM (SELECT school FROM users UNION ALL SELECT school FROM listings);
+
+INSERT INTO schools (name)
+    SELECT MIN(e.school) FROM existing_schools e
+    WHERE NOT EXISTS (SELECT 1 FROM schools s WHERE LOWER(s.name) = LOWER(e.school))
+        AND NOT EXISTS (SELECT 1 FROM school_aliases a WHERE a.alias = LOWER(e.school))
+    GROUP BY LOWER(e.school);
+
+CREATE TEMPORARY TABLE school_matches AS
+    SELECT e.school, COALESCE(
+        (SELECT s.id FROM schools s WHERE LOWER(s.name) = LOWER(e.school)),
+        (SELECT a.school_id FROM school_aliases a WHERE a.alias = LOWER(e.school))
+    ) AS school_id
+    FROM existing_schools e;
+
+-- SQLite can't add a NOT NULL column without a default, so school_id is only required by the application
+ALTER TABLE users ADD COLUMN school_id INTEGER REFERENCES schools (id);
+UPDATE users SET school_id = m.school_id
+    FROM school_matches m WHERE m.school = COALESCE(NULLIF(TRIM(users.school), ''), 'Unknown');
+ALTER TABLE users DROP COLUMN school;
+
+-- Listings keep the canonical name in school for full-text search, school_id is what they are filtered by
+ALTER TABLE listings ADD COLUMN school_id INTEGER REFERENCES schools (id);
+UPDATE listings SET school_id = m.school_id
+    FROM school_matches m WHERE m.school = COALESCE(NULLIF(TRIM(listings.school), ''), 'Unknown');
+UPDATE listings SET school = (SELECT s.name FROM schools s WHERE s.id = listings.school_id);
+
+DROP INDEX IF EXISTS listings_school_idx;
+CREATE INDEX IF NOT EXISTS listings_school_id_idx ON listings (school_id);
+
+DROP TABLE existing_schools;
+DROP TABLE school_matches;
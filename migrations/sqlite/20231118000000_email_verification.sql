@@ -0,0 +1,15 @@
+-- School email addresses, stored lowercase, and whether the user has confirmed theirs
+ALTER TABLE users ADD COLUMN email TEXT;
+ALTER TABLE users ADD COLUMN verified BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE UNIQUE INDEX IF NOT EXISTS users_email_idx ON users (email);
+
+-- One-time links sent to confirm an email address
+CREATE TABLE IF NOT EXISTS email_verifications (
+    token TEXT PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    email TEXT NOT NULL,
+    expiry_date INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS email_verifications_user_id_idx ON email_verifications (user_id);
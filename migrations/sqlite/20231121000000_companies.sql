@@ -0,0 +1,32 @@
+-- Companies with one canonical name, so ratings can be combined across all of a company's listings
+CREATE TABLE IF NOT EXISTS companies (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    -- Website domain, like example.com
+    domain TEXT,
+    logo_url TEXT
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS companies_name_idx ON companies (LOWER(name));
+
+-- Other names a company goes by, stored lowercase
+CREATE TABLE IF NOT EXISTS company_aliases (
+    alias TEXT PRIMARY KEY,
+    company_id INTEGER NOT NULL REFERENCES companies (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS company_aliases_company_id_idx ON company_aliases (company_id);
+
+-- Each differently cased company name becomes one company. Blank ones become "Unknown".
+INSERT OR IGNORE INTO companies (name)
+    SELECT MIN(COALESCE(NULLIF(TRIM(company), ''), 'Unknown')) FROM listings
+    GROUP BY LOWER(COALESCE(NULLIF(TRIM(company), ''), 'Unknown'));
+
+-- Listings keep the canonical name in company for searching and sorting, company_id is what they are grouped by.
+-- SQLite can't add a NOT NULL column without a default, so company_id is only required by the application.
+ALTER TABLE listings ADD COLUMN company_id INTEGER REFERENCES companies (id);
+UPDATE listings SET company_id = c.id
+    FROM companies c WHERE LOWER(c.name) = LOWER(COALESCE(NULLIF(TRIM(listings.company), ''), 'Unknown'));
+UPDATE listings SET company = (SELECT c.name FROM companies c WHERE c.id = listings.company_id);
+
+CREATE INDEX IF NOT EXISTS listings_company_ref_idx ON listings (company_id);
@@ -0,0 +1,7 @@
+-- Optional ratings for each part of the internship, from 0 to 1 like rating
+ALTER TABLE comments ADD COLUMN mentorship REAL CHECK (mentorship BETWEEN 0 AND 1);
+ALTER TABLE comments ADD COLUMN compensation REAL CHECK (compensation BETWEEN 0 AND 1);
+ALTER TABLE comments ADD COLUMN work_life_balance REAL CHECK (work_life_balance BETWEEN 0 AND 1);
+ALTER TABLE comments ADD COLUMN learning REAL CHECK (learning BETWEEN 0 AND 1);
+-- How likely a return offer is
+ALTER TABLE comments ADD COLUMN return_offer REAL CHECK (return_offer BETWEEN 0 AND 1);
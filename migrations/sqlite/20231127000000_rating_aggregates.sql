@@ -0,0 +1,22 @@
+-- Ratings stored on each listing and company, so previews don't have to load every comment.
+-- The application recomputes them from the comments whenever one is added, and for every listing after migrating.
+ALTER TABLE listings ADD COLUMN rating_average REAL NOT NULL DEFAULT 0;
+ALTER TABLE listings ADD COLUMN rating_count INTEGER NOT NULL DEFAULT 0;
+-- Number of comments with 1 to 5 stars
+ALTER TABLE listings ADD COLUMN stars_1 INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE listings ADD COLUMN stars_2 INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE listings ADD COLUMN stars_3 INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE listings ADD COLUMN stars_4 INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE listings ADD COLUMN stars_5 INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE companies ADD COLUMN rating_average REAL NOT NULL DEFAULT 0;
+ALTER TABLE companies ADD COLUMN rating_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE companies ADD COLUMN stars_1 INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE companies ADD COLUMN stars_2 INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE companies ADD COLUMN stars_3 INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE companies ADD COLUMN stars_4 INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE companies ADD COLUMN stars_5 INTEGER NOT NULL DEFAULT 0;
+
+-- Supports sorting by the stored ratings
+CREATE INDEX IF NOT EXISTS listings_rating_average_id_idx ON listings (rating_average, id);
+CREATE INDEX IF NOT EXISTS listings_rating_count_id_idx ON listings (rating_count, id);
@@ -0,0 +1,18 @@
+-- What people were paid for an internship. Only shown summed up over several reports.
+CREATE TABLE IF NOT EXISTS compensation_reports (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    listing_id INTEGER NOT NULL REFERENCES listings (id) ON DELETE CASCADE,
+    author TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    pay REAL NOT NULL CHECK (pay > 0),
+    pay_period TEXT NOT NULL CHECK (pay_period IN ('hourly', 'monthly')),
+    -- Dollars per month
+    housing_stipend REAL CHECK (housing_stipend >= 0),
+    -- One-time amounts in dollars
+    relocation REAL CHECK (relocation >= 0),
+    signing_bonus REAL CHECK (signing_bonus >= 0),
+    year INTEGER NOT NULL,
+    location TEXT NOT NULL DEFAULT ''
+);
+
+CREATE INDEX IF NOT EXISTS compensation_reports_listing_id_idx ON compensation_reports (listing_id);
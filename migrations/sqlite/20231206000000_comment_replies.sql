@@ -0,0 +1,4 @@
+-- Replies point at the comment they answer. Replies have no rating of their own.
+ALTER TABLE comments ADD COLUMN parent_id INTEGER REFERENCES comments (id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS comments_parent_id_idx ON comments (parent_id);
@@ -0,0 +1,7 @@
+-- Helpful (1) and unhelpful (-1) votes on comments, one per user per comment
+CREATE TABLE IF NOT EXISTS comment_votes (
+    comment_id INTEGER NOT NULL REFERENCES comments (id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    value INTEGER NOT NULL CHECK (value IN (1, -1)),
+    PRIMARY KEY (comment_id, user_id)
+);
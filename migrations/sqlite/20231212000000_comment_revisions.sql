@@ -0,0 +1,18 @@
+-- When the comment was last edited, NULL if it never was
+ALTER TABLE comments ADD COLUMN edited_at INTEGER;
+
+-- Earlier versions of edited comments. timestamp is when that version was posted or last edited.
+CREATE TABLE IF NOT EXISTS comment_revisions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    comment_id INTEGER NOT NULL REFERENCES comments (id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    rating REAL NOT NULL,
+    mentorship REAL,
+    compensation REAL,
+    work_life_balance REAL,
+    learning REAL,
+    return_offer REAL,
+    timestamp INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS comment_revisions_comment_id_idx ON comment_revisions (comment_id);
@@ -0,0 +1,28 @@
+-- The user who posted the comment. Comments are still shown under author, this is what ties them to an account.
+ALTER TABLE comments ADD COLUMN user_id INTEGER REFERENCES users (id) ON DELETE SET NULL;
+UPDATE comments SET user_id = (SELECT u.id FROM users u WHERE u.name = comments.author);
+
+-- Each user keeps only their newest review on a listing. Their older reviews become its revisions and replies to them move to it.
+-- The stored ratings are recomputed by the application after migrating.
+CREATE TEMPORARY TABLE duplicate_reviews AS
+    SELECT c.id, (
+        SELECT k.id FROM comments k
+        WHERE k.listing_id = c.listing_id AND k.user_id = c.user_id AND k.parent_id IS NULL
+        ORDER BY k.timestamp DESC, k.id DESC LIMIT 1
+    ) AS kept_id
+    FROM comments c WHERE c.parent_id IS NULL AND c.user_id IS NOT NULL;
+DELETE FROM duplicate_reviews WHERE id = kept_id;
+
+UPDATE comment_revisions SET comment_id = (SELECT d.kept_id FROM duplicate_reviews d WHERE d.id = comment_revisions.comment_id)
+WHERE comment_id IN (SELECT id FROM duplicate_reviews);
+INSERT INTO comment_revisions (comment_id, content, rating, mentorship, compensation, work_life_balance, learning, return_offer, timestamp)
+    SELECT d.kept_id, c.content, c.rating, c.mentorship, c.compensation, c.work_life_balance, c.learning, c.return_offer, COALESCE(c.edited_at, c.timestamp)
+    FROM duplicate_reviews d JOIN comments c ON c.id = d.id;
+UPDATE comments SET edited_at = COALESCE(edited_at, timestamp) WHERE id IN (SELECT kept_id FROM duplicate_reviews);
+UPDATE comments SET parent_id = (SELECT d.kept_id FROM duplicate_reviews d WHERE d.id = comments.parent_id)
+WHERE parent_id IN (SELECT id FROM duplicate_reviews);
+DELETE FROM comments WHERE id IN (SELECT id FROM duplicate_reviews);
+DROP TABLE duplicate_reviews;
+
+-- Replies are unrated, so a user can leave any number of them
+CREATE UNIQUE INDEX IF NOT EXISTS comments_one_review_idx ON comments (listing_id, user_id) WHERE parent_id IS NULL;
@@ -0,0 +1,39 @@
+-- Hidden comments and listings stay in the database for moderators but aren't shown or counted in ratings
+ALTER TABLE comments ADD COLUMN hidden BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE listings ADD COLUMN hidden BOOLEAN NOT NULL DEFAULT FALSE;
+-- The account that posted the listing. Listings posted before this was recorded have none.
+ALTER TABLE listings ADD COLUMN user_id INTEGER REFERENCES users (id) ON DELETE SET NULL;
+-- Banned users can't log in
+ALTER TABLE users ADD COLUMN banned BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Users flagging a comment or a listing, at most once each
+CREATE TABLE IF NOT EXISTS reports (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    reporter_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    comment_id INTEGER REFERENCES comments (id) ON DELETE CASCADE,
+    listing_id INTEGER REFERENCES listings (id) ON DELETE CASCADE,
+    reason TEXT NOT NULL CHECK (reason IN ('spam', 'harassment', 'fake', 'offensive', 'other')),
+    details TEXT NOT NULL DEFAULT '',
+    timestamp INTEGER NOT NULL,
+    -- When a moderator dealt with it, NULL while it is open
+    resolved_at INTEGER,
+    CHECK ((comment_id IS NULL) <> (listing_id IS NULL)),
+    UNIQUE (reporter_id, comment_id),
+    UNIQUE (reporter_id, listing_id)
+);
+
+CREATE INDEX IF NOT EXISTS reports_open_idx ON reports (timestamp) WHERE resolved_at IS NULL;
+
+-- Every action a moderator takes. The ids aren't foreign keys so entries outlive what they point at.
+CREATE TABLE IF NOT EXISTS moderation_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    moderator_id INTEGER NOT NULL,
+    action TEXT NOT NULL CHECK (action IN ('dismiss', 'hide', 'delete', 'warn', 'ban')),
+    report_id INTEGER,
+    comment_id INTEGER,
+    listing_id INTEGER,
+    -- The author of the reported content
+    user_id INTEGER,
+    note TEXT NOT NULL DEFAULT '',
+    timestamp INTEGER NOT NULL
+);
@@ -0,0 +1,4 @@
+-- From least to most trusted. School admins manage the moderators of their own school.
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'user' CHECK (role IN ('user', 'moderator', 'school_admin', 'site_admin'));
+
+CREATE INDEX IF NOT EXISTS users_staff_idx ON users (school_id) WHERE role <> 'user';
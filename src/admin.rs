@@ -0,0 +1,154 @@
+use leptos::*;
+use leptos_meta::Title;
+use cfg_if::cfg_if;
+use serde::{Deserialize, Serialize};
+use crate::profile::get_profile;
+use crate::role::Role;
+
+cfg_if! {
+	if #[cfg(feature = "ssr")] {
+		use crate::store::store;
+		use crate::role::require_role;
+	}
+}
+
+// A user with a role above User, as listed on the admin page
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StaffMember {
+    pub name: String,
+    pub school: String,
+    pub role: Role,
+}
+
+// Moderators and admins, only from their own school for school admins
+#[server(GetStaff, "/staff")]
+pub async fn get_staff() -> Result<Result<Vec<StaffMember>, String>, ServerFnError> {
+    let Some(admin) = require_role(Role::SchoolAdmin).await? else {
+        return Ok(Err("Only admins can see who moderates".to_string()));
+    };
+
+    let school_id = (!admin.role.includes(Role::SiteAdmin)).then_some(admin.school_id);
+
+    let mut staff = Vec::new();
+    for user in store()?.get_staff(school_id).await? {
+        let school = store()?.get_school(user.school_id).await?.map(|school| school.name).unwrap_or_default();
+        staff.push(StaffMember { name: user.name, school, role: user.role });
+    }
+    Ok(Ok(staff))
+}
+
+// Gives the named user a role. School admins can only make or unmake moderators at their own school.
+#[server(SetUserRole, "/set-role")]
+pub async fn set_user_role(name: String, role: Role) -> Result<Result<(), String>, ServerFnError> {
+    let Some(admin) = require_role(Role::SchoolAdmin).await? else {
+        return Ok(Err("Only admins can change roles".to_string()));
+    };
+
+    let Some(user) = store()?.get_user_by_name(name.trim()).await? else {
+        return Ok(Err(format!("There is no user named {}", name.trim())));
+    };
+
+    // So the last admin can't lock everyone out
+    if user.id == admin.id {
+        return Ok(Err("You can't change your own role".to_string()));
+    }
+
+    if !admin.role.can_assign(role) || !admin.role.can_assign(user.role) {
+        return Ok(Err("Only site admins can appoint or remove admins".to_string()));
+    }
+
+    if !admin.role.includes(Role::SiteAdmin) && user.school_id != admin.school_id {
+        return Ok(Err("You can only manage users from your school".to_string()));
+    }
+
+    store()?.set_role(user.id, role).await?;
+    Ok(Ok(()))
+}
+
+/// Renders the moderators and admins, with a form to change anyone's role, for school and site admins
+#[component]
+pub fn AdminPage() -> impl IntoView {
+    let profile = create_resource(|| (), |_| async move { get_profile().await.ok().flatten() });
+    let admin_role = move || profile.get().flatten().map_or(Role::User, |profile| profile.get_role());
+    let staff = create_resource(|| (), |_| async move { get_staff().await });
+
+    let (name, set_name) = create_signal(String::new());
+    let (role, set_role) = create_signal(Role::Moderator);
+    let (status, set_status) = create_signal(String::new());
+
+    let change_role = move |name: String, role: Role| {
+        spawn_local(async move {
+            match set_user_role(name.clone(), role).await {
+                Ok(Ok(())) => {
+                    set_status(format!("{} is now a {}", name, role.label().to_lowercase()));
+                    staff.refetch();
+                },
+                Ok(Err(e)) => set_status(e),
+                Err(e) => set_status("Failed to change role: ".to_string() + e.to_string().as_str()),
+            }
+        });
+    };
+
+    let on_submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        change_role(name.get_untracked(), role.get_untracked());
+        set_name(String::new());
+    };
+
+    view! {
+        <Title text="Admin"/>
+        <div class="admin">
+            <h1>"Admin"</h1>
+            <Show when=move || admin_role().includes(Role::SchoolAdmin) fallback=|| view! { <p>"Only admins can manage roles."</p> }>
+                <form class="admin-form" on:submit=on_submit>
+                    <input type="text"
+                        placeholder="Username"
+                        prop:value=name
+                        on:input=move |ev| set_name(event_target_value(&ev))
+                    />
+                    <select on:change=move |ev| {
+                        let value = event_target_value(&ev);
+                        if let Some(option) = Role::ALL.into_iter().find(|option| format!("{:?}", option) == value) {
+                            set_role(option);
+                        }
+                    }>
+                        {move || Role::ALL.into_iter().filter(|option| admin_role().can_assign(*option)).map(|option| view! {
+                            <option value=format!("{:?}", option) selected=move || role.get() == option>{option.label()}</option>
+                        }).collect_view()}
+                    </select>
+                    <input class="comment-submit" type="submit" value="Set role"/>
+                </form>
+                <p class="comment-status">{status}</p>
+                <Suspense fallback=move || view! { <p>"Loading..."</p> }>
+                    {move || staff.get().map(|staff| match staff {
+                        Ok(Ok(members)) if members.is_empty() => view! { <p>"No moderators or admins yet."</p> }.into_view(),
+                        Ok(Ok(members)) => view! {
+                            <table class="admin-staff">
+                                {members.into_iter().map(|member| {
+                                    let removable = admin_role().can_assign(member.role);
+                                    let name = member.name.clone();
+
+                                    view! {
+                                        <tr>
+                                            <td>{member.name}</td>
+                                            <td>{member.school}</td>
+                                            <td>{member.role.label()}</td>
+                                            <td>
+                                                {removable.then(|| view! {
+                                                    <button class="comment-action" on:click=move |_| change_role(name.clone(), Role::User)>"Remove role"</button>
+                                                })}
+                                            </td>
+                                        </tr>
+                                    }
+                                }).collect_view()}
+                            </table>
+                        }.into_view(),
+                        Ok(Err(e)) => view! { <p>{e}</p> }.into_view(),
+                        Err(_) => view! { <p>"Failed to load moderators"</p> }.into_view(),
+                    })}
+                </Suspense>
+            </Show>
+            <a href="/">"Back to listings"</a>
+        </div>
+    }
+}
@@ -5,7 +5,7 @@ use leptos::leptos_dom::Text;
 use wasm_bindgen::JsCast;
 use crate::listing::ListingPage;
 use crate::listing::Listing;
-use crate::listing::get_all_listings;
+use crate::listing::{get_all_listings, get_listings, search_listings, ListingCursor, ListingFilters, ListingSort, Season, WorkMode};
 use crate::header::Header;
 use crate::search_bar::SearchBar;
 use crate::listing_prev::ListingPrev;
@@ -13,7 +13,14 @@ use crate::login::Login;
 use leptos::leptos_dom::logging::console_log;
 use crate::registration::Registration;
 use crate::session::SessionPage;
-use crate::profile::Profile;
+use crate::profile::{get_profile, Profile};
+use crate::session::get_school_only;
+use crate::new_post::NewPost;
+use crate::verify_email::VerifyEmailPage;
+use crate::company::CompanyPage;
+use crate::moderation::ModerationPage;
+use crate::admin::AdminPage;
+use crate::role::Role;
 
 #[component]
 pub fn App() -> impl IntoView {
@@ -33,6 +40,12 @@ pub fn App() -> impl IntoView {
             <main>
                 <Routes>
                     <Route path="" view=HomePage/>
+                    <Route path="/listing/:id" view=ListingPage ssr=SsrMode::Async/>
+                    <Route path="/company/:id" view=CompanyPage ssr=SsrMode::Async/>
+                    <Route path="/new-post" view=NewPost/>
+                    <Route path="/verify-email" view=VerifyEmailPage/>
+                    <Route path="/moderation" view=ModerationPage/>
+                    <Route path="/admin" view=AdminPage/>
                     <Route path="/*any" view=NotFound/>
                 </Routes>
             </main>
@@ -40,20 +53,60 @@ pub fn App() -> impl IntoView {
     }
 }
 
+// Where "Load more" continues from: the next page of search results, or a cursor when browsing
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+enum NextPage {
+	Search(u32),
+	Browse(ListingCursor),
+}
+
+// Fetches one page of listings, searching when there is a query and browsing in the chosen order otherwise.
+// With a school id, only that school's listings are fetched.
+async fn fetch_listings(query: String, school_id: Option<i64>, filters: ListingFilters, sort: ListingSort, next: Option<NextPage>) -> Result<(Vec<Listing>, Option<NextPage>), ServerFnError> {
+	if query.trim().is_empty() {
+		let cursor = match next {
+			Some(NextPage::Browse(cursor)) => Some(cursor),
+			_ => None,
+		};
+		let batch = match school_id {
+			Some(school_id) => get_listings(school_id, filters, sort, cursor).await?.map_err(ServerFnError::ServerError)?,
+			None => get_all_listings(filters, sort, cursor).await?,
+		};
+		Ok((batch.listings, batch.next_cursor.map(NextPage::Browse)))
+	} else {
+		let page = match next {
+			Some(NextPage::Search(page)) => page,
+			_ => 0,
+		};
+		let filters = ListingFilters { school_id: school_id.or(filters.school_id), ..filters };
+		let results = search_listings(query, filters, page).await?;
+		Ok((results.listings, results.has_more.then_some(NextPage::Search(page + 1))))
+	}
+}
+
+pub fn listing_previews(listings: Vec<Listing>) -> View {
+	listings.into_iter().map(|listing| view! {
+		<ListingPrev
+			company_name=listing.get_company().clone()
+			position=listing.get_position().clone()
+			description=listing.get_description().clone()
+			id=listing.get_id()
+			tags=listing.get_tags()
+			rating=listing.get_rating()
+			reviews=listing.get_reviews()
+		/>
+	}).collect_view()
+}
+
 /// Renders the home page of your application.
 #[component]
 fn HomePage() -> impl IntoView {
 
-	let all_listings = create_resource(
-		|| (),
-		|_| async move { get_all_listings().await },
-	);
-
-	console_log(&format!("all_listings: {:?}", all_listings.get()));
-
 	let (search_query, set_search_query) = create_signal(String::new());
 
-    let (filtered_listings, set_filtered_listings) = create_signal::<View>(View::Text(Text::new("No results".to_string().into())));
+	let (sort, set_sort) = create_signal(ListingSort::default());
+
+	let (filters, set_filters) = create_signal(ListingFilters::default());
 
     let profile_open = create_rw_signal(false);
 
@@ -63,44 +116,156 @@ fn HomePage() -> impl IntoView {
 
     let reload_profile = create_rw_signal(false);
 
+	// The logged in user's school, refetched after logging in or out
+	let profile = create_resource(
+		move || reload_profile.get(),
+		|_| async move { get_profile().await.ok().flatten() },
+	);
+	let user_school = Signal::derive(move || profile.get().flatten().map(|profile| profile.get_school().clone()));
+	let user_role = Signal::derive(move || profile.get().flatten().map_or(Role::User, |profile| profile.get_role()));
+
+	// The header toggle, starting from the choice saved in the session
+	let school_only = create_rw_signal(false);
+	let saved_school_only = create_resource(|| (), |_| async move { get_school_only().await.unwrap_or(false) });
+	create_effect(move |_| {
+		if let Some(saved) = saved_school_only.get() {
+			school_only.set(saved);
+		}
+	});
+
+	// Anonymous visitors always see every school
+	let school_id = move || user_school.get().filter(|_| school_only.get()).map(|school| school.id);
+
+	// The first page is a resource so it renders on the server, later pages are appended by "Load more"
+	let first_page = create_resource(
+		move || (search_query.get(), school_id(), filters.get(), sort.get()),
+		|(query, school_id, filters, sort)| fetch_listings(query, school_id, filters, sort, None),
+	);
+
+	let (more_listings, set_more_listings) = create_signal(Vec::<Listing>::new());
+
+	let (next_page, set_next_page) = create_signal(None::<NextPage>);
+
+	let (loading_more, set_loading_more) = create_signal(false);
+
+	create_effect(move |_| {
+		set_more_listings(Vec::new());
+		match first_page.get() {
+			Some(Ok((_, next))) => set_next_page(next),
+			_ => set_next_page(None),
+		}
+	});
+
+	let load_more = move |_| {
+		let Some(next) = next_page.get_untracked() else {
+			return;
+		};
+
+		set_loading_more(true);
+		spawn_local(async move {
+			if let Ok((listings, next)) = fetch_listings(search_query.get_untracked(), untrack(school_id), filters.get_untracked(), sort.get_untracked(), Some(next)).await {
+				set_more_listings.update(|more| more.extend(listings));
+				set_next_page(next);
+			}
+			set_loading_more(false);
+		});
+	};
+
     view! {
 		<div class="home-page">
-            <Header profile_open=profile_open login_open=login_open/>
+            <Header profile_open=profile_open login_open=login_open school=user_school school_only=school_only role=user_role/>
             <SearchBar 
                 search_query=search_query 
                 set_search_query=set_search_query
             />
-			<Suspense fallback=move || view! { <div>"Loading..."</div> }>
-            	<div>{filtered_listings}</div>
-			</Suspense>
-            {
-                create_effect(move |_| {
-                    let filter_text = search_query.get().to_lowercase();
-					let all_listings = match all_listings.get() {
-						Some(Ok(listings)) => listings,
-						Some(Err(_)) => return,
-						None => return,
-					};
-					if all_listings.is_empty() {
-						return;
+			<div class="listing-filters">
+				<input
+					type="text"
+					placeholder="Location"
+					on:change=move |ev| {
+						let value = event_target_value(&ev);
+						let value = value.trim();
+						set_filters.update(|filters| filters.location = (!value.is_empty()).then(|| value.to_string()));
+					}
+				/>
+				<select
+					name="listing-work-mode"
+					on:change=move |ev| {
+						let value = event_target_value(&ev);
+						set_filters.update(|filters| filters.work_mode = WorkMode::ALL.into_iter().find(|mode| format!("{:?}", mode) == value));
+					}
+				>
+					<option value="">"Any work mode"</option>
+					{WorkMode::ALL.into_iter().map(|mode| view! {
+						<option value=format!("{:?}", mode)>{mode.label()}</option>
+					}).collect_view()}
+				</select>
+				<select
+					name="listing-season"
+					on:change=move |ev| {
+						let value = event_target_value(&ev);
+						set_filters.update(|filters| filters.season = Season::ALL.into_iter().find(|season| format!("{:?}", season) == value));
 					}
-					let filtered = all_listings.iter().filter(|listing| {
-						listing.get_company().to_lowercase().contains(&filter_text) || listing.get_position().to_lowercase().contains(&filter_text)
-					}).collect::<Vec<&Listing>>();
-					let content = filtered.iter().map(|listing| {
-						view! {
-							<ListingPrev
-								company_name=listing.get_company().clone()
-								position=listing.get_position().clone()
-								description=listing.get_description().clone()
-								id=listing.get_id()
-							/>
+				>
+					<option value="">"Any season"</option>
+					{Season::ALL.into_iter().map(|season| view! {
+						<option value=format!("{:?}", season)>{season.label()}</option>
+					}).collect_view()}
+				</select>
+				<input
+					type="number"
+					placeholder="Year"
+					on:change=move |ev| {
+						let year = event_target_value(&ev).trim().parse().ok();
+						set_filters.update(|filters| filters.year = year);
+					}
+				/>
+				<label>
+					<input
+						type="checkbox"
+						on:change=move |ev| {
+							// Unchecked shows paid and unpaid listings alike
+							let checked = event_target_checked(&ev);
+							set_filters.update(|filters| filters.paid = checked.then_some(true));
 						}
-					}).collect_view();
-
-					set_filtered_listings(content);
-                });
-            }
+					/>
+					"Paid only"
+				</label>
+			</div>
+			// Search results are ordered by relevance, so sorting only applies while browsing
+			<Show when=move || search_query.with(|query| query.trim().is_empty()) fallback=|| ()>
+				<div class="listing-controls">
+					<label for="listing-sort"><b>"Sort by "</b></label>
+					<select
+						name="listing-sort"
+						on:change=move |ev| {
+							let value = event_target_value(&ev);
+							if let Some(option) = ListingSort::ALL.into_iter().find(|option| format!("{:?}", option) == value) {
+								set_sort(option);
+							}
+						}
+					>
+						{ListingSort::ALL.into_iter().map(|option| view! {
+							<option value=format!("{:?}", option) selected=move || sort.get() == option>{option.label()}</option>
+						}).collect_view()}
+					</select>
+				</div>
+			</Show>
+			<Suspense fallback=move || view! { <div>"Loading..."</div> }>
+				{move || first_page.get().map(|result| match result {
+					Ok((listings, _)) if listings.is_empty() => view! { <div>"No results"</div> }.into_view(),
+					Ok((listings, _)) => view! { <div>{listing_previews(listings)}</div> }.into_view(),
+					Err(_) => view! { <div>"Failed to load listings"</div> }.into_view(),
+				})}
+				<div>{move || listing_previews(more_listings.get())}</div>
+				<Show when=move || next_page.with(|next| next.is_some()) fallback=|| ()>
+					<div class="pagination">
+						<button on:click=load_more disabled=loading_more>
+							{move || if loading_more.get() { "Loading..." } else { "Load more" }}
+						</button>
+					</div>
+				</Show>
+			</Suspense>
         </div>
         <Profile open=profile_open reload_profile=reload_profile/>
         <Login open=login_open reload_profile=reload_profile register_open=register_open/>
@@ -109,18 +274,9 @@ fn HomePage() -> impl IntoView {
 }
 
 
-/// Renders the new post page of your application.
-/// This is where users can create new posts.
-#[component]
-fn NewPost() -> impl IntoView {
-	view! {
-		<h1>"New Post"</h1>
-	}
-}
-
 /// 404 - Not Found
 #[component]
-fn NotFound() -> impl IntoView {
+pub fn NotFound() -> impl IntoView {
     // set an HTTP status code 404
     // this is feature gated because it can only be done during
     // initial server-side rendering
@@ -2,6 +2,9 @@ use leptos::*;
 use cfg_if::cfg_if;
 use serde::{Deserialize, Serialize};
 use chrono::prelude::*;
+use crate::profile::ProfileData;
+use crate::moderation::{ReportButton, ReportTarget};
+use crate::role::Role;
 
 
 /*
@@ -11,35 +14,200 @@ HOW TO WRITE A COMMENT:
     String::from("Bob"),
     String::from("Hi. My name is bob. How is your day?"),
     26764,
-    0.65
+    0.65,
+    1,
+    1,
 )/>
 */
 
 cfg_if! {
 	if #[cfg(feature = "ssr")] {
-		use crate::db::db;
+		use crate::store::store;
+		use crate::login::current_user;
+		use crate::role::require_role;
+
+		// Checks a review before it is posted or edited
+		fn check_review(content: &str, rating: f64, dimensions: &DimensionRatings) -> Result<(), String> {
+			if content.trim().is_empty() {
+				return Err("Comment can't be empty".to_string());
+			}
+
+			if !(0.0..=1.0).contains(&rating) {
+				return Err("Rating must be between 0 and 5 stars".to_string());
+			}
+
+			if dimensions.values().into_iter().flatten().any(|value| !(0.0..=1.0).contains(&value)) {
+				return Err("Ratings must be between 0 and 5 stars".to_string());
+			}
+
+			Ok(())
+		}
+
+		impl Comment {
+			// Like "Verified University of California, Los Angeles student #3"
+			fn pseudonym_label(&self) -> String {
+				format!(
+					"{}{} #{}",
+					if self.author_verified { "Verified " } else { "" },
+					self.author_school.as_ref().map_or("Anonymous student".to_string(), |school| format!("{} student", school)),
+					self.pseudonym.unwrap_or_default(),
+				)
+			}
+
+			// Marks the viewer's own comments and swaps the author of anonymous comments for their pseudonym.
+			// Moderators still see who the author is.
+			fn for_viewer(mut self, viewer: Option<i32>, moderator: bool) -> Comment {
+				// Nobody can act on a deleted comment, so who wrote it isn't sent
+				if self.deleted {
+					self.author = "[deleted]".to_string();
+					return self;
+				}
+
+				self.own = viewer.is_some() && self.user_id == viewer;
+
+				if self.anonymous {
+					let pseudonym = self.pseudonym_label();
+					self.author = if moderator { format!("{} ({})", pseudonym, self.author) } else { pseudonym };
+				}
+				self
+			}
+		}
+    }
+}
+
+// Ratings for each part of the internship, from 0 to 1 like Comment.rating. Reviewers can skip any of them.
+//
+// Also used for averages, where a dimension nobody rated is None.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct DimensionRatings {
+    pub mentorship: Option<f64>,
+    pub compensation: Option<f64>,
+    pub work_life_balance: Option<f64>,
+    pub learning: Option<f64>,
+    // How likely a return offer is
+    pub return_offer: Option<f64>,
+}
+
+impl DimensionRatings {
+    pub const LABELS: [&'static str; 5] = ["Mentorship", "Compensation", "Work-life balance", "Learning", "Return offer"];
+
+    pub fn values(&self) -> [Option<f64>; 5] {
+        [self.mentorship, self.compensation, self.work_life_balance, self.learning, self.return_offer]
+    }
+
+    pub fn from_values(values: [Option<f64>; 5]) -> Self {
+        let [mentorship, compensation, work_life_balance, learning, return_offer] = values;
+        DimensionRatings { mentorship, compensation, work_life_balance, learning, return_offer }
+    }
+
+    // Labels paired with the dimensions that have a rating
+    pub fn rated(&self) -> Vec<(&'static str, f64)> {
+        Self::LABELS.into_iter()
+            .zip(self.values())
+            .filter_map(|(label, value)| value.map(|value| (label, value)))
+            .collect()
+    }
+
+    // Averages each dimension over the ratings that have it
+    pub fn average<'a>(ratings: impl IntoIterator<Item = &'a DimensionRatings>) -> Self {
+        let mut sums = [(0.0, 0); 5];
+        for ratings in ratings {
+            for (sum, value) in sums.iter_mut().zip(ratings.values()) {
+                if let Some(value) = value {
+                    sum.0 += value;
+                    sum.1 += 1;
+                }
+            }
+        }
+
+        Self::from_values(sums.map(|(sum, count)| (count > 0).then(|| sum / count as f64)))
     }
 }
 
 // Struct for comment data
 #[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
 pub struct Comment {
-    author: String,
-    content: String,
-    timestamp: i64,
-    rating: f64,
-    listing_id: i64,
+    pub(crate) author: String,
+    pub(crate) content: String,
+    pub(crate) timestamp: i64,
+    pub(crate) rating: f64,
+    pub(crate) listing_id: i64,
+    pub(crate) id: i64,
+    // Whether the author has verified their school email. Looked up when comments are loaded, not stored.
+    pub(crate) author_verified: bool,
+    #[cfg_attr(feature = "ssr", sqlx(flatten))]
+    pub(crate) dimensions: DimensionRatings,
+    // The comment this replies to. Replies have no rating and don't count towards the listing's ratings.
+    pub(crate) parent_id: Option<i64>,
+    // Helpful votes minus unhelpful ones. Looked up when comments are loaded, not stored.
+    pub(crate) helpful: i64,
+    // The logged in user's vote on this comment: 1, -1, or 0 for none
+    pub(crate) viewer_vote: i32,
+    // When the author last edited the comment, None if they never did
+    pub(crate) edited_at: Option<i64>,
+    // The account that posted the comment, None for comments older than accounts. Never sent to the client.
+    #[serde(skip)]
+    pub(crate) user_id: Option<i32>,
+    // Shown under a pseudonym instead of the author's name
+    pub(crate) anonymous: bool,
+    // The number the author posts anonymously under on this listing, if they ever have
+    #[serde(skip)]
+    pub(crate) pseudonym: Option<i64>,
+    // The name of the author's school, for their pseudonym
+    #[serde(skip)]
+    pub(crate) author_school: Option<String>,
+    // Whether the logged in user posted this comment. Filled in by get_comments, not stored.
+    #[cfg_attr(feature = "ssr", sqlx(default))]
+    pub(crate) own: bool,
+    // Hidden by a moderator. get_comments leaves hidden comments out.
+    pub(crate) hidden: bool,
+    // Deleted after others replied. Kept blank so the replies stay threaded, and not counted in ratings.
+    pub(crate) deleted: bool,
+    // The user who deleted it. Only a comment its author deleted can be posted over again.
+    #[serde(skip)]
+    pub(crate) deleted_by: Option<i32>,
+}
+
+// An earlier version of an edited comment, kept so moderators can see what was changed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct CommentRevision {
+    pub id: i64,
+    pub comment_id: i64,
+    pub content: String,
+    pub rating: f64,
+    #[cfg_attr(feature = "ssr", sqlx(flatten))]
+    pub dimensions: DimensionRatings,
+    // When this version was posted or last edited
+    pub timestamp: i64,
 }
 
 // Implementation of getters for comment data
 impl Comment {
-    pub fn new(author: String, content: String, timestamp: i64, rating:f64, listing_id:i64) -> Self {
+    pub fn new(author: String, content: String, timestamp: i64, rating:f64, listing_id:i64, id: i64) -> Self {
         Comment {
             author,
             content,
             timestamp,
             rating,
             listing_id,
+            id,
+            author_verified: false,
+            dimensions: DimensionRatings::default(),
+            parent_id: None,
+            helpful: 0,
+            viewer_vote: 0,
+            edited_at: None,
+            user_id: None,
+            anonymous: false,
+            pseudonym: None,
+            author_school: None,
+            own: false,
+            hidden: false,
+            deleted: false,
+            deleted_by: None,
         }
     }
 
@@ -62,23 +230,287 @@ impl Comment {
     pub fn get_listing_id(&self) -> i64 {
         self.listing_id
     }
+
+    pub fn get_id(&self) -> i64 {
+        self.id
+    }
+
+    pub fn is_author_verified(&self) -> bool {
+        self.author_verified
+    }
+
+    pub fn get_dimensions(&self) -> DimensionRatings {
+        self.dimensions
+    }
+
+    pub fn get_parent_id(&self) -> Option<i64> {
+        self.parent_id
+    }
+
+    pub fn is_reply(&self) -> bool {
+        self.parent_id.is_some()
+    }
+
+    pub fn get_helpful(&self) -> i64 {
+        self.helpful
+    }
+
+    pub fn get_viewer_vote(&self) -> i32 {
+        self.viewer_vote
+    }
+
+    pub fn get_edited_at(&self) -> Option<i64> {
+        self.edited_at
+    }
+
+    pub fn is_anonymous(&self) -> bool {
+        self.anonymous
+    }
+
+    pub fn is_own(&self) -> bool {
+        self.own
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.deleted
+    }
 }
 
+// Orders for the comments on a listing. Replies are ordered the same way under their parent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CommentSort {
+    #[default]
+    Helpful,
+    Newest,
+    HighestRated,
+    LowestRated,
+}
+
+impl CommentSort {
+    pub const ALL: [CommentSort; 4] = [CommentSort::Helpful, CommentSort::Newest, CommentSort::HighestRated, CommentSort::LowestRated];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CommentSort::Helpful => "Most helpful",
+            CommentSort::Newest => "Newest",
+            CommentSort::HighestRated => "Highest rated",
+            CommentSort::LowestRated => "Lowest rated",
+        }
+    }
+}
+
+// Deepest a reply can be nested. Reviews are at depth 0, replies to them at depth 1.
+pub const MAX_REPLY_DEPTH: usize = 3;
+
 #[server(GetComments, "/comments")]
-pub async fn get_comments(listing_id: i64) -> Result<Vec<Comment>, ServerFnError> {
-    let mut conn = db().await?;
-    let comments = sqlx::query_as!(Comment, "SELECT * FROM comments WHERE listing_id = $1", listing_id)
-        .fetch_all(&mut conn).await?;
-    Ok(comments)
+pub async fn get_comments(listing_id: i64, sort: CommentSort) -> Result<Vec<Comment>, ServerFnError> {
+    let user = current_user().await?;
+    let viewer = user.as_ref().map(|user| user.id);
+    let moderator = user.as_ref().map_or(false, |user| user.role.includes(Role::Moderator));
+
+    let comments = store()?.get_comments(listing_id, sort, viewer).await?;
+    Ok(comments.into_iter().map(|comment| comment.for_viewer(viewer, moderator)).collect())
+}
+
+// Votes a comment helpful (1) or unhelpful (-1) as the logged in user, replacing their earlier vote
+#[server(VoteComment, "/vote_comment")]
+pub async fn vote_comment(comment_id: i64, value: i32) -> Result<Result<(), String>, ServerFnError> {
+    let Some(user) = current_user().await? else {
+        return Ok(Err("You must be logged in to vote".to_string()));
+    };
+
+    if value != 1 && value != -1 {
+        return Ok(Err("Votes must be 1 or -1".to_string()));
+    }
+
+    let Some(comment) = store()?.get_comment(comment_id).await? else {
+        return Ok(Err("This comment no longer exists".to_string()));
+    };
+
+    if comment.user_id == Some(user.id) {
+        return Ok(Err("You can't vote on your own comment".to_string()));
+    }
+
+    store()?.set_vote(comment_id, user.id, value).await?;
+    Ok(Ok(()))
+}
+
+#[server(RetractVote, "/retract_vote")]
+pub async fn retract_vote(comment_id: i64) -> Result<Result<(), String>, ServerFnError> {
+    let Some(user) = current_user().await? else {
+        return Ok(Err("You must be logged in to vote".to_string()));
+    };
+
+    store()?.remove_vote(comment_id, user.id).await?;
+    Ok(Ok(()))
 }
 
+// Posts a review as the logged in user, or updates their review if they already left one.
+// The author always comes from the session, never from the client.
 #[server(AddComment, "/add_comment")]
-pub async fn add_comment(author: String, content: String, rating: f64, listing_id: i64) -> Result<(), ServerFnError> {
-    let mut conn = db().await?;
-    let rows = sqlx::query!("INSERT INTO comments (author, content, timestamp, rating, listing_id) VALUES ($1, $2, $3, $4, $5)",
-        author, content, chrono::Utc::now().timestamp_millis(), rating, listing_id)
-        .execute(&mut conn).await?;
-    Ok(())
+pub async fn add_comment(content: String, rating: f64, dimensions: DimensionRatings, listing_id: i64, anonymous: bool) -> Result<Result<(), String>, ServerFnError> {
+    let Some(user) = current_user().await? else {
+        return Ok(Err("You must be logged in to comment".to_string()));
+    };
+
+    if let Err(e) = check_review(&content, rating, &dimensions) {
+        return Ok(Err(e));
+    }
+
+    // Each user has one review per listing, so posting again updates it and keeps the old version as a revision.
+    // A review its author deleted is brought back this way, but not one a moderator hid or deleted.
+    loop {
+        if let Some(review) = store()?.get_review(listing_id, user.id).await? {
+            if review.hidden || (review.deleted && review.deleted_by != Some(user.id)) {
+                return Ok(Err("Your review of this listing was removed by a moderator".to_string()));
+            }
+
+            // Switching would tie the pseudonym to the name, or the other way around
+            if review.anonymous != anonymous {
+                let posted = if review.anonymous { "anonymously" } else { "under your name" };
+                return Ok(Err(format!("You posted your review of this listing {}, and it has to stay that way", posted)));
+            }
+
+            let restore = review.deleted;
+            let edited = Comment {
+                content,
+                rating,
+                dimensions,
+                edited_at: Some(chrono::Utc::now().timestamp_millis()),
+                ..review
+            };
+            if restore {
+                store()?.restore_comment(edited).await?;
+            } else {
+                store()?.edit_comment(edited).await?;
+            }
+            return Ok(Ok(()));
+        }
+
+        // The id is assigned by the store
+        let comment = Comment {
+            dimensions,
+            user_id: Some(user.id),
+            anonymous,
+            ..Comment::new(user.name.clone(), content.clone(), chrono::Utc::now().timestamp_millis(), rating, listing_id, 0)
+        };
+
+        // Not added when another request from the user added their review first, which is then updated instead
+        if store()?.add_comment(comment).await? {
+            return Ok(Ok(()));
+        }
+    }
+}
+
+// Replies to a comment as the logged in user, on the same listing
+#[server(AddReply, "/add_reply")]
+pub async fn add_reply(content: String, parent_id: i64, anonymous: bool) -> Result<Result<(), String>, ServerFnError> {
+    let Some(user) = current_user().await? else {
+        return Ok(Err("You must be logged in to reply".to_string()));
+    };
+
+    if content.trim().is_empty() {
+        return Ok(Err("Reply can't be empty".to_string()));
+    }
+
+    let Some(parent) = store()?.get_comment(parent_id).await? else {
+        return Ok(Err("The comment you're replying to no longer exists".to_string()));
+    };
+
+    if parent.deleted {
+        return Ok(Err("You can't reply to a deleted comment".to_string()));
+    }
+
+    // Walks up to the review the thread starts from to find how deep the parent is
+    let mut depth = 0;
+    let mut ancestor = parent.parent_id;
+    while let Some(id) = ancestor {
+        depth += 1;
+        ancestor = store()?.get_comment(id).await?.and_then(|comment| comment.parent_id);
+    }
+
+    if depth >= MAX_REPLY_DEPTH {
+        return Ok(Err("This thread is nested too deeply to reply to".to_string()));
+    }
+
+    // The id is assigned by the store
+    let reply = Comment {
+        parent_id: Some(parent.id),
+        user_id: Some(user.id),
+        anonymous,
+        ..Comment::new(user.name, content, chrono::Utc::now().timestamp_millis(), 0.0, parent.listing_id, 0)
+    };
+    store()?.add_comment(reply).await?;
+    Ok(Ok(()))
+}
+
+// Changes the logged in user's own comment, keeping the version it replaces. Replies only have their content changed.
+#[server(EditComment, "/edit_comment")]
+pub async fn edit_comment(comment_id: i64, content: String, rating: f64, dimensions: DimensionRatings) -> Result<Result<(), String>, ServerFnError> {
+    let Some(user) = current_user().await? else {
+        return Ok(Err("You must be logged in to edit comments".to_string()));
+    };
+
+    let Some(comment) = store()?.get_comment(comment_id).await? else {
+        return Ok(Err("This comment no longer exists".to_string()));
+    };
+
+    if comment.user_id != Some(user.id) {
+        return Ok(Err("You can only edit your own comments".to_string()));
+    }
+
+    if comment.deleted {
+        return Ok(Err("This comment was deleted".to_string()));
+    }
+
+    let (rating, dimensions) = if comment.is_reply() { (comment.rating, comment.dimensions) } else { (rating, dimensions) };
+
+    if let Err(e) = check_review(&content, rating, &dimensions) {
+        return Ok(Err(e));
+    }
+
+    let edited = Comment {
+        content,
+        rating,
+        dimensions,
+        edited_at: Some(chrono::Utc::now().timestamp_millis()),
+        ..comment
+    };
+    store()?.edit_comment(edited).await?;
+    Ok(Ok(()))
+}
+
+// Deletes the logged in user's own comment. One with replies is kept blank so the replies stay.
+#[server(DeleteComment, "/delete_comment")]
+pub async fn delete_comment(comment_id: i64) -> Result<Result<(), String>, ServerFnError> {
+    let Some(user) = current_user().await? else {
+        return Ok(Err("You must be logged in to delete comments".to_string()));
+    };
+
+    let Some(comment) = store()?.get_comment(comment_id).await? else {
+        return Ok(Err("This comment no longer exists".to_string()));
+    };
+
+    if comment.user_id != Some(user.id) {
+        return Ok(Err("You can only delete your own comments".to_string()));
+    }
+
+    if comment.deleted {
+        return Ok(Err("This comment was already deleted".to_string()));
+    }
+
+    store()?.delete_comment(comment_id, user.id).await?;
+    Ok(Ok(()))
+}
+
+// Earlier versions of a comment, newest first. Only moderators can see them.
+#[server(GetCommentHistory, "/comment_history")]
+pub async fn get_comment_history(comment_id: i64) -> Result<Result<Vec<CommentRevision>, String>, ServerFnError> {
+    if require_role(Role::Moderator).await?.is_none() {
+        return Ok(Err("Only moderators can see edit history".to_string()));
+    }
+
+    Ok(Ok(store()?.get_comment_revisions(comment_id).await?))
 }
 
 // Renders a navbar structure
@@ -116,19 +548,337 @@ pub fn Comment(
             <div class="comment-header"> 
                 <div class="comment-profile">
                     {comment_data.get_author()}
+                    // Pseudonyms already say whether the author is verified
+                    {(comment_data.is_author_verified() && !comment_data.is_anonymous()).then(|| view! { <span class="verified-badge" title="Verified student">"✓ Verified"</span> })}
                 </div>
-                <div class="star-rating">
-                    <div class="stars">
-                        {star_icons}
+                {(!comment_data.is_reply() && !comment_data.is_deleted()).then(|| view! {
+                    <div class="star-rating">
+                        <div class="stars">
+                            {star_icons}
+                        </div>
                     </div>
-                </div>
+                })}
                 <div class ="comment-timestamp">
-                {DateTime::from_timestamp(comment_data.get_timestamp()/1000,0).expect("invalid timestamp").format("%h %d %Y %I:%M %p").to_string()}
+                {format_timestamp(comment_data.get_timestamp())}
+                {comment_data.get_edited_at().map(|edited_at| view! {
+                    <span class="comment-edited">" · edited "{format_timestamp(edited_at)}</span>
+                })}
                 </div>
             </div>
             <div class="comment-content">
-                {comment_data.get_content()}
+                {if comment_data.is_deleted() { "[deleted]".to_string() } else { comment_data.get_content().clone() }}
             </div>
+            {(!comment_data.is_deleted()).then(|| dimension_breakdown(comment_data.get_dimensions()))}
         </div>
     }
-}
\ No newline at end of file
+}
+
+// Like "Dec 12 2023 03:04 PM", from milliseconds since the epoch
+pub fn format_timestamp(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp/1000,0).expect("invalid timestamp").format("%h %d %Y %I:%M %p").to_string()
+}
+
+// Lists the rated dimensions with their stars out of 5, or nothing if none are rated
+pub fn dimension_breakdown(ratings: DimensionRatings) -> View {
+    let rated = ratings.rated();
+
+    if rated.is_empty() {
+        return ().into_view();
+    }
+
+    view! {
+        <ul class="rating-dimensions">
+            {rated.into_iter().map(|(label, value)| view! {
+                <li>
+                    <span class="rating-dimension-label">{label}</span>
+                    <span class="rating-dimension-value">{format!("{:.1}", value * 5.0)}" ★"</span>
+                </li>
+            }).collect_view()}
+        </ul>
+    }.into_view()
+}
+
+// A row of 5 clickable stars. Clicking the selected star again clears the rating.
+#[component]
+pub fn RatingInput(label: &'static str, value: RwSignal<Option<u8>>) -> impl IntoView {
+    view! {
+        <div class="rating-input">
+            <span class="rating-input-label">{label}</span>
+            {(1..=5u8).map(|star| view! {
+                <span
+                    class="rating-input-star"
+                    class:filled=move || value.get().map_or(false, |value| star <= value)
+                    on:click=move |_| value.update(|value| *value = if *value == Some(star) { None } else { Some(star) })
+                >
+                    "★"
+                </span>
+            }).collect_view()}
+        </div>
+    }
+}
+/// Renders a comment with its replies under it, which can be collapsed.
+/// comments holds every comment on the listing, replies are picked out of it by parent id.
+/// reload_ratings is set when an edit or delete changes the listing's ratings.
+#[component]
+pub fn CommentThread(
+    comment: Comment,
+    comments: Resource<(i64, CommentSort), Vec<Comment>>,
+    depth: usize,
+    #[prop(into)] viewer: Signal<Option<ProfileData>>,
+    reload_ratings: RwSignal<bool>,
+) -> impl IntoView {
+    let id = comment.id;
+    let comment = store_value(comment);
+    // Read from the latest fetch, since the thread itself is kept when the comments are refetched
+    let latest = move || comments.with(|comments| {
+        comments.as_ref()
+            .and_then(|comments| comments.iter().find(|comment| comment.id == id).cloned())
+            .unwrap_or_else(|| comment.get_value())
+    });
+    let helpful = move || latest().helpful;
+    let viewer_vote = move || latest().viewer_vote;
+    let replies = move || comments.get().unwrap_or_default().into_iter()
+        .filter(|reply| reply.parent_id == Some(id))
+        .collect::<Vec<_>>();
+
+    let logged_in = move || viewer.with(|viewer| viewer.is_some());
+    let is_author = move || latest().own;
+    let is_deleted = move || latest().deleted;
+    let is_moderator = move || viewer.with(|viewer| viewer.as_ref().map_or(false, |viewer| viewer.has_role(Role::Moderator)));
+
+    let (collapsed, set_collapsed) = create_signal(false);
+    let (replying, set_replying) = create_signal(false);
+    let (reply_content, set_reply_content) = create_signal(String::new());
+    let (reply_anonymous, set_reply_anonymous) = create_signal(false);
+    let (status, set_status) = create_signal(String::new());
+
+    let (editing, set_editing) = create_signal(false);
+    let (edit_content, set_edit_content) = create_signal(String::new());
+    let edit_rating = create_rw_signal(None::<u8>);
+    let edit_dimensions = DimensionRatings::LABELS.map(|_| create_rw_signal(None::<u8>));
+    // Loaded when a moderator opens it
+    let (history, set_history) = create_signal(None::<Vec<CommentRevision>>);
+
+    let on_reply = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        let content = reply_content.get_untracked();
+        let anonymous = reply_anonymous.get_untracked();
+
+        spawn_local(async move {
+            match add_reply(content, id, anonymous).await {
+                Ok(Ok(())) => {
+                    set_reply_content(String::new());
+                    set_status(String::new());
+                    set_replying(false);
+                    set_collapsed(false);
+                    comments.refetch();
+                },
+                Ok(Err(e)) => set_status(e),
+                Err(e) => set_status("Failed to post reply: ".to_string() + e.to_string().as_str()),
+            }
+        });
+    };
+
+    // Clicking the vote already cast takes it back
+    let on_vote = move |value: i32| {
+        let retract = untrack(viewer_vote) == value;
+
+        spawn_local(async move {
+            let result = if retract { retract_vote(id).await } else { vote_comment(id, value).await };
+
+            match result {
+                Ok(Ok(())) => {
+                    set_status(String::new());
+                    comments.refetch();
+                },
+                Ok(Err(e)) => set_status(e),
+                Err(e) => set_status("Failed to vote: ".to_string() + e.to_string().as_str()),
+            }
+        });
+    };
+
+    // Fills the edit form with the comment as it is now
+    let start_editing = move |_| {
+        let current = untrack(latest);
+        set_edit_content(current.content);
+        edit_rating.set(Some((current.rating * 5.0).round() as u8));
+        for (input, value) in edit_dimensions.into_iter().zip(current.dimensions.values()) {
+            input.set(value.map(|value| (value * 5.0).round() as u8));
+        }
+        set_editing(true);
+    };
+
+    let on_edit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        let content = edit_content.get_untracked();
+        let rating = edit_rating.get_untracked().unwrap_or(0) as f64 / 5.0;
+        let dimensions = DimensionRatings::from_values(edit_dimensions.map(|input| input.get_untracked().map(|stars| stars as f64 / 5.0)));
+
+        spawn_local(async move {
+            match edit_comment(id, content, rating, dimensions).await {
+                Ok(Ok(())) => {
+                    set_status(String::new());
+                    set_editing(false);
+                    set_history(None);
+                    comments.refetch();
+                    reload_ratings.set(true);
+                },
+                Ok(Err(e)) => set_status(e),
+                Err(e) => set_status("Failed to edit comment: ".to_string() + e.to_string().as_str()),
+            }
+        });
+    };
+
+    let on_delete = move |_| {
+        if !window().confirm_with_message("Delete this comment?").unwrap_or(false) {
+            return;
+        }
+
+        spawn_local(async move {
+            match delete_comment(id).await {
+                Ok(Ok(())) => {
+                    comments.refetch();
+                    reload_ratings.set(true);
+                },
+                Ok(Err(e)) => set_status(e),
+                Err(e) => set_status("Failed to delete comment: ".to_string() + e.to_string().as_str()),
+            }
+        });
+    };
+
+    let toggle_history = move |_| {
+        if history.get_untracked().is_some() {
+            set_history(None);
+            return;
+        }
+
+        spawn_local(async move {
+            match get_comment_history(id).await {
+                Ok(Ok(revisions)) => set_history(Some(revisions)),
+                Ok(Err(e)) => set_status(e),
+                Err(e) => set_status("Failed to load history: ".to_string() + e.to_string().as_str()),
+            }
+        });
+    };
+
+    view! {
+        <div class="comment-thread">
+            {move || view! { <Comment comment_data=latest()/> }}
+            <div class="comment-actions">
+                <div class="comment-votes">
+                    <button
+                        class="comment-vote"
+                        class:voted=move || viewer_vote() == 1
+                        title="Helpful"
+                        disabled=move || !logged_in() || is_deleted()
+                        on:click=move |_| on_vote(1)
+                    >
+                        "▲"
+                    </button>
+                    <span class="comment-helpful">{helpful}</span>
+                    <button
+                        class="comment-vote"
+                        class:voted=move || viewer_vote() == -1
+                        title="Not helpful"
+                        disabled=move || !logged_in() || is_deleted()
+                        on:click=move |_| on_vote(-1)
+                    >
+                        "▼"
+                    </button>
+                </div>
+                <Show when=move || logged_in() && !is_deleted() && depth < MAX_REPLY_DEPTH fallback=|| ()>
+                    <button class="comment-action" on:click=move |_| set_replying.update(|replying| *replying = !*replying)>
+                        {move || if replying.get() { "Cancel" } else { "Reply" }}
+                    </button>
+                </Show>
+                <Show when=is_author fallback=|| ()>
+                    <Show
+                        when=move || editing.get()
+                        fallback=move || view! { <button class="comment-action" on:click=start_editing>"Edit"</button> }
+                    >
+                        <button class="comment-action" on:click=move |_| set_editing(false)>"Cancel edit"</button>
+                    </Show>
+                    <button class="comment-action" on:click=on_delete>"Delete"</button>
+                </Show>
+                <Show when=move || is_moderator() && latest().edited_at.is_some() fallback=|| ()>
+                    <button class="comment-action" on:click=toggle_history>
+                        {move || if history.with(Option::is_some) { "Hide history" } else { "History" }}
+                    </button>
+                </Show>
+                <ReportButton target=ReportTarget::Comment(id) logged_in=Signal::derive(move || logged_in() && !is_author() && !is_deleted())/>
+                <Show when=move || !replies().is_empty() fallback=|| ()>
+                    <button class="comment-action" on:click=move |_| set_collapsed.update(|collapsed| *collapsed = !*collapsed)>
+                        {move || {
+                            let count = replies().len();
+                            let noun = if count == 1 { "reply" } else { "replies" };
+                            if collapsed.get() { format!("Show {} {}", count, noun) } else { format!("Hide {}", noun) }
+                        }}
+                    </button>
+                </Show>
+            </div>
+            <p class="comment-status">{status}</p>
+            <Show when=move || editing.get() fallback=|| ()>
+                <form class="edit-form" on:submit=on_edit>
+                    <input class="comment-box" type="text"
+                        prop:value=edit_content
+                        on:input=move |ev| set_edit_content(event_target_value(&ev))
+                    />
+                    // Replies have no ratings to change
+                    <Show when=move || !latest().is_reply() fallback=|| ()>
+                        <div class="rating-inputs">
+                            <RatingInput label="Overall" value=edit_rating/>
+                            {DimensionRatings::LABELS.into_iter().zip(edit_dimensions).map(|(label, value)| view! {
+                                <RatingInput label=label value=value/>
+                            }).collect_view()}
+                        </div>
+                    </Show>
+                    <input class="comment-submit" type="submit" value="Save"/>
+                </form>
+            </Show>
+            {move || history.get().map(|revisions| view! {
+                <div class="comment-history">
+                    {if revisions.is_empty() { Some(view! { <p>"No earlier versions"</p> }) } else { None }}
+                    {revisions.into_iter().map(|revision| view! {
+                        <div class="comment-revision">
+                            <div class="comment-timestamp">
+                                {format_timestamp(revision.timestamp)}
+                                {(!latest().is_reply()).then(|| format!(" · {:.1} ★", revision.rating * 5.0))}
+                            </div>
+                            <div class="comment-content">{revision.content}</div>
+                            {dimension_breakdown(revision.dimensions)}
+                        </div>
+                    }).collect_view()}
+                </div>
+            })}
+            <Show when=move || replying.get() fallback=|| ()>
+                <form class="reply-form" on:submit=on_reply>
+                    <input class="comment-box" type="text"
+                        prop:value=reply_content
+                        on:input=move |ev| set_reply_content(event_target_value(&ev))
+                        placeholder="Write your reply here."
+                    />
+                    <label class="comment-anonymous">
+                        <input type="checkbox"
+                            prop:checked=reply_anonymous
+                            on:change=move |ev| set_reply_anonymous(event_target_checked(&ev))
+                        />
+                        "Post anonymously"
+                    </label>
+                    <input class="comment-submit" type="submit" value="Reply"/>
+                </form>
+            </Show>
+            <Show when=move || !collapsed.get() fallback=|| ()>
+                <div class="comment-replies">
+                    <For
+                        each=replies
+                        key=|reply| reply.id
+                        // Views are erased here because a component's type can't contain itself
+                        children=move |reply: Comment| view! {
+                            <CommentThread comment=reply comments=comments depth={depth + 1} viewer=viewer reload_ratings=reload_ratings/>
+                        }.into_view()
+                    />
+                </div>
+            </Show>
+        </div>
+    }
+}
@@ -0,0 +1,159 @@
+use leptos::*;
+use leptos_meta::Title;
+use leptos_router::*;
+use cfg_if::cfg_if;
+use serde::{Deserialize, Serialize};
+use crate::app::{listing_previews, NotFound};
+use crate::comment::{dimension_breakdown, DimensionRatings};
+use crate::listing::{get_all_listings, Listing, ListingCursor, ListingFilters, ListingSort};
+use crate::rating::{rating_histogram, RatingSummary};
+use crate::interview::{get_interview_stats, interview_stats_summary};
+use crate::compensation::{compensation_summary, get_company_compensation};
+
+cfg_if! {
+	if #[cfg(feature = "ssr")] {
+		use crate::store::store;
+	}
+}
+
+// A company under its canonical name. Listings are linked to it by company_id.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct Company {
+    pub id: i64,
+    pub name: String,
+    // Website domain, like example.com
+    pub domain: Option<String>,
+    pub logo_url: Option<String>,
+}
+
+// A company along with the reviews left on all of its listings
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompanyDetails {
+    pub company: Company,
+    pub ratings: RatingSummary,
+    // Average of each dimension over the reviews that rated it
+    pub dimensions: DimensionRatings,
+}
+
+#[server(GetCompany, "/company")]
+pub async fn get_company(id: i64) -> Result<Option<CompanyDetails>, ServerFnError> {
+    store()?.get_company(id).await
+}
+
+#[derive(Params, PartialEq, Clone)]
+struct CompanyParams {
+    id: i64,
+}
+
+/// Renders a company with the ratings and listings of all its internships, across years and schools
+#[component]
+pub fn CompanyPage() -> impl IntoView {
+    let params = use_params::<CompanyParams>();
+    let id = move || params.with(|params| params.as_ref().map(|params| params.id).ok());
+
+    let company = create_resource(
+        id,
+        |id| async move {
+            match id {
+                Some(id) => get_company(id).await,
+                None => Ok(None),
+            }
+        },
+    );
+
+    let interview_stats = create_resource(
+        id,
+        |id| async move {
+            match id {
+                Some(id) => get_interview_stats(id).await.unwrap_or_default(),
+                None => Default::default(),
+            }
+        },
+    );
+
+    let compensation = create_resource(
+        id,
+        |id| async move {
+            match id {
+                Some(id) => get_company_compensation(id).await.unwrap_or_default(),
+                None => Default::default(),
+            }
+        },
+    );
+
+    let (listings, set_listings) = create_signal(Vec::<Listing>::new());
+    let (next_cursor, set_next_cursor) = create_signal(None::<ListingCursor>);
+    let (has_more, set_has_more) = create_signal(false);
+    let (loading, set_loading) = create_signal(false);
+
+    let load_listings = move |company_id: i64, cursor: Option<ListingCursor>| {
+        set_loading(true);
+        spawn_local(async move {
+            let filters = ListingFilters { company_id: Some(company_id), ..Default::default() };
+
+            if let Ok(batch) = get_all_listings(filters, ListingSort::Newest, cursor).await {
+                set_listings.update(|listings| listings.extend(batch.listings));
+                set_has_more(batch.next_cursor.is_some());
+                set_next_cursor(batch.next_cursor);
+            }
+            set_loading(false);
+        });
+    };
+
+    // Starts over whenever the page switches to another company
+    create_effect(move |_| {
+        set_listings(Vec::new());
+        set_has_more(false);
+        if let Some(id) = id() {
+            load_listings(id, None);
+        }
+    });
+
+    let load_more = move |_| {
+        if let Some(id) = id() {
+            load_listings(id, next_cursor.get_untracked());
+        }
+    };
+
+    view! {
+        <Suspense fallback=move || view! { <p>"Loading..."</p> }>
+            {move || company.get().map(|details| match details {
+                Ok(Some(details)) => {
+                    let CompanyDetails { company, ratings, dimensions } = details;
+
+                    view! {
+                        <Title text=company.name.clone()/>
+                        <div class="company">
+                            <div class="company-header">
+                                {company.logo_url.clone().map(|logo_url| view! { <img class="company-logo" src=logo_url alt=""/> })}
+                                <h1>{company.name.clone()}</h1>
+                                {company.domain.clone().map(|domain| view! {
+                                    <a class="company-website" href=format!("https://{}", domain) target="_blank">{domain}</a>
+                                })}
+                            </div>
+                            <p class="company-rating">{ratings.label()}</p>
+                            {rating_histogram(&ratings)}
+                            {dimension_breakdown(dimensions)}
+                            {move || interview_stats.get().map(|stats| interview_stats_summary(&stats))}
+                            <h2>"Compensation"</h2>
+                            {move || compensation.get().map(|stats| compensation_summary(&stats))}
+                        </div>
+                    }.into_view()
+                },
+                Ok(None) => view! { <NotFound/> }.into_view(),
+                Err(_) => view! { <p>"Failed to load company"</p> }.into_view(),
+            })}
+        </Suspense>
+        <div class="company-listings">
+            {move || listing_previews(listings.get())}
+            <Show when=move || has_more.get() fallback=|| ()>
+                <div class="pagination">
+                    <button on:click=load_more disabled=loading>
+                        {move || if loading.get() { "Loading..." } else { "Load more" }}
+                    </button>
+                </div>
+            </Show>
+        </div>
+    }
+}
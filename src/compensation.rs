@@ -0,0 +1,475 @@
+use leptos::*;
+use cfg_if::cfg_if;
+use serde::{Deserialize, Serialize};
+
+cfg_if! {
+	if #[cfg(feature = "ssr")] {
+		use crate::store::store;
+		use crate::login::current_user;
+
+		// Reported value at fraction p of the way through sorted values, by nearest rank, rounded to the dollar.
+		// Never averaging neighbours keeps a published figure from being solved back into the values behind it.
+		fn percentile(sorted: &[f64], p: f64) -> f64 {
+			let rank = (p * sorted.len() as f64).ceil() as usize;
+			sorted[rank.clamp(1, sorted.len()) - 1].round()
+		}
+
+		// Median of a subset of the reports, or None unless more than MIN_REPORTS have one.
+		// A subset exactly at the threshold could be compared with the overall figures to single someone out.
+		fn suppressed_median(values: impl IntoIterator<Item = f64>) -> Option<f64> {
+			let mut values: Vec<f64> = values.into_iter().collect();
+			if (values.len() as i64) <= MIN_REPORTS {
+				return None;
+			}
+
+			values.sort_by(f64::total_cmp);
+			Some(percentile(&values, 0.5))
+		}
+
+		// Sums the reports up. Anything backed by fewer than MIN_REPORTS reports is left out.
+		fn compensation_stats(reports: Vec<CompensationReport>) -> CompensationStats {
+			let count = reports.len() as i64;
+			if count < MIN_REPORTS {
+				return CompensationStats { reports: count, ..Default::default() };
+			}
+
+			let mut hourly: Vec<f64> = reports.iter().map(CompensationReport::hourly_rate).collect();
+			hourly.sort_by(f64::total_cmp);
+
+			let mut years: Vec<i32> = reports.iter().map(|report| report.year).collect();
+			years.sort();
+			years.dedup();
+
+			let by_year = years.into_iter()
+				.filter_map(|year| {
+					let rates: Vec<f64> = reports.iter().filter(|report| report.year == year).map(CompensationReport::hourly_rate).collect();
+					let reports = rates.len() as i64;
+					suppressed_median(rates).map(|median| YearlyPay { year, median, reports })
+				})
+				.collect();
+
+			CompensationStats {
+				reports: count,
+				hourly: Some(PayRange {
+					median: percentile(&hourly, 0.5),
+					middle_half: (count >= MIN_RANGE_REPORTS).then(|| (percentile(&hourly, 0.25), percentile(&hourly, 0.75))),
+				}),
+				housing_stipend: suppressed_median(reports.iter().filter_map(|report| report.housing_stipend)),
+				relocation: suppressed_median(reports.iter().filter_map(|report| report.relocation)),
+				signing_bonus: suppressed_median(reports.iter().filter_map(|report| report.signing_bonus)),
+				by_year,
+			}
+		}
+	}
+}
+
+// Fewest reports any statistic is shown for, so no single submission can be picked out
+pub const MIN_REPORTS: i64 = 5;
+
+// Fewest reports the middle half of pay is shown for. With only a few reports the quartiles sit next to the median
+// and together give away most of the values.
+pub const MIN_RANGE_REPORTS: i64 = 10;
+
+// Full time hours in a month, for comparing monthly pay with hourly pay
+const HOURS_PER_MONTH: f64 = 40.0 * 52.0 / 12.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::Type))]
+#[cfg_attr(feature = "ssr", sqlx(type_name = "pay_period", rename_all = "lowercase"))]
+pub enum PayPeriod {
+    Hourly,
+    Monthly,
+}
+
+impl PayPeriod {
+    pub const ALL: [PayPeriod; 2] = [PayPeriod::Hourly, PayPeriod::Monthly];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PayPeriod::Hourly => "per hour",
+            PayPeriod::Monthly => "per month",
+        }
+    }
+}
+
+// What someone was paid for an internship. Only ever shown summed up with other reports.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct CompensationReport {
+    pub id: i64,
+    pub listing_id: i64,
+    pub author: String,
+    // Milliseconds since the epoch, like comment timestamps
+    pub timestamp: i64,
+    pub pay: f64,
+    pub pay_period: PayPeriod,
+    // Dollars per month
+    pub housing_stipend: Option<f64>,
+    // One-time amounts in dollars
+    pub relocation: Option<f64>,
+    pub signing_bonus: Option<f64>,
+    pub year: i32,
+    pub location: String,
+}
+
+impl CompensationReport {
+    pub fn hourly_rate(&self) -> f64 {
+        match self.pay_period {
+            PayPeriod::Hourly => self.pay,
+            PayPeriod::Monthly => self.pay / HOURS_PER_MONTH,
+        }
+    }
+}
+
+// A compensation report as submitted through the form. The author and timestamp are filled in by the server.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NewCompensationReport {
+    pub listing_id: i64,
+    pub pay: f64,
+    pub pay_period: Option<PayPeriod>,
+    pub housing_stipend: Option<f64>,
+    pub relocation: Option<f64>,
+    pub signing_bonus: Option<f64>,
+    pub year: Option<i32>,
+    pub location: String,
+}
+
+impl NewCompensationReport {
+    // Returns the pay period and year, which the form leaves optional
+    pub fn validate(&self) -> Result<(PayPeriod, i32), String> {
+        if !self.pay.is_finite() || self.pay <= 0.0 {
+            return Err("Pay must be more than 0".to_string());
+        }
+        let Some(pay_period) = self.pay_period else {
+            return Err("Choose whether the pay is hourly or monthly".to_string());
+        };
+        if [self.housing_stipend, self.relocation, self.signing_bonus].into_iter().flatten().any(|amount| !amount.is_finite() || amount < 0.0) {
+            return Err("Amounts can't be negative".to_string());
+        }
+        match self.year {
+            Some(year) if (2000..=2100).contains(&year) => Ok((pay_period, year)),
+            _ => Err("Enter the year of the internship".to_string()),
+        }
+    }
+}
+
+// Median hourly pay, with the 25th and 75th percentiles once there are MIN_RANGE_REPORTS reports.
+// Every figure is a whole dollar amount.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PayRange {
+    pub median: f64,
+    pub middle_half: Option<(f64, f64)>,
+}
+
+// Median hourly pay for one year, rounded to the dollar
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct YearlyPay {
+    pub year: i32,
+    pub median: f64,
+    pub reports: i64,
+}
+
+// Compensation reports summed up. Parts with too few reports behind them are None or left out.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompensationStats {
+    pub reports: i64,
+    pub hourly: Option<PayRange>,
+    // Medians of the reports that included each one
+    pub housing_stipend: Option<f64>,
+    pub relocation: Option<f64>,
+    pub signing_bonus: Option<f64>,
+    // Oldest year first
+    pub by_year: Vec<YearlyPay>,
+}
+
+#[server(GetListingCompensation, "/listing-compensation")]
+pub async fn get_listing_compensation(listing_id: i64) -> Result<CompensationStats, ServerFnError> {
+    let reports = store()?.get_compensation_reports(listing_id).await?;
+    Ok(compensation_stats(reports))
+}
+
+#[server(GetCompanyCompensation, "/company-compensation")]
+pub async fn get_company_compensation(company_id: i64) -> Result<CompensationStats, ServerFnError> {
+    let reports = store()?.get_company_compensation_reports(company_id).await?;
+    Ok(compensation_stats(reports))
+}
+
+// Submits a compensation report as the logged in user
+#[server(AddCompensationReport, "/add-compensation-report")]
+pub async fn add_compensation_report(report: NewCompensationReport) -> Result<Result<(), String>, ServerFnError> {
+    let Some(user) = current_user().await? else {
+        return Ok(Err("You must be logged in to report compensation".to_string()));
+    };
+
+    let (pay_period, year) = match report.validate() {
+        Ok(valid) => valid,
+        Err(e) => return Ok(Err(e)),
+    };
+
+    if store()?.get_listing(report.listing_id).await?.is_none() {
+        return Ok(Err("This listing no longer exists".to_string()));
+    }
+
+    // The id is assigned by the store
+    store()?.add_compensation_report(CompensationReport {
+        id: 0,
+        listing_id: report.listing_id,
+        author: user.name,
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        pay: report.pay,
+        pay_period,
+        housing_stipend: report.housing_stipend,
+        relocation: report.relocation,
+        signing_bonus: report.signing_bonus,
+        year,
+        location: report.location.trim().to_string(),
+    }).await?;
+    Ok(Ok(()))
+}
+
+// Pay range, extras and the median by year, with the change from the year before
+pub fn compensation_summary(stats: &CompensationStats) -> View {
+    let Some(hourly) = stats.hourly else {
+        return view! {
+            <p class="compensation-suppressed">
+                {format!("Pay is shown once at least {} people have reported it", MIN_REPORTS)}
+            </p>
+        }.into_view();
+    };
+
+    let extras: Vec<String> = [
+        (stats.housing_stipend, "housing stipend per month"),
+        (stats.relocation, "relocation"),
+        (stats.signing_bonus, "signing bonus"),
+    ].into_iter()
+        .filter_map(|(amount, label)| amount.map(|amount| format!("${:.0} {}", amount, label)))
+        .collect();
+
+    let mut previous = None::<f64>;
+    let by_year = stats.by_year.iter().map(|yearly| {
+        let change = previous.map(|previous| format!(" ({:+.0}%)", (yearly.median / previous - 1.0) * 100.0));
+        previous = Some(yearly.median);
+
+        view! {
+            <li>{format!("{}: ${:.0}/hr{}", yearly.year, yearly.median, change.unwrap_or_default())}</li>
+        }
+    }).collect_view();
+
+    view! {
+        <div class="compensation-stats">
+            <p class="compensation-median">
+                {format!("${:.0}/hr median", hourly.median)}
+                {hourly.middle_half.map(|(p25, p75)| view! {
+                    <span class="compensation-range">{format!(" (${:.0} to ${:.0} for the middle half)", p25, p75)}</span>
+                })}
+            </p>
+            {(!extras.is_empty()).then(|| view! { <p class="compensation-extras">"Typical extras: "{extras.join(", ")}</p> })}
+            {(stats.by_year.len() > 1).then(|| view! { <ul class="compensation-years">{by_year}</ul> })}
+            <p class="compensation-count">{format!("From {} reports", stats.reports)}</p>
+        </div>
+    }.into_view()
+}
+
+/// Renders the pay reported for a listing, with a form to report yours when logged in
+#[component]
+pub fn CompensationSection(listing_id: i64, #[prop(into)] logged_in: Signal<bool>) -> impl IntoView {
+    let stats = create_resource(
+        move || listing_id,
+        |listing_id| async move { get_listing_compensation(listing_id).await.unwrap_or_default() },
+    );
+
+    let (pay, set_pay) = create_signal("".to_string());
+    let (pay_period, set_pay_period) = create_signal(Some(PayPeriod::Hourly));
+    let (housing_stipend, set_housing_stipend) = create_signal("".to_string());
+    let (relocation, set_relocation) = create_signal("".to_string());
+    let (signing_bonus, set_signing_bonus) = create_signal("".to_string());
+    let (year, set_year) = create_signal("".to_string());
+    let (location, set_location) = create_signal("".to_string());
+    let (status, set_status) = create_signal("".to_string());
+
+    // Blank optional amounts are left out
+    let amount = |value: String| value.trim().parse::<f64>().ok();
+
+    let on_submit = move |_| {
+        let report = NewCompensationReport {
+            listing_id,
+            pay: amount(pay.get()).unwrap_or(0.0),
+            pay_period: pay_period.get(),
+            housing_stipend: amount(housing_stipend.get()),
+            relocation: amount(relocation.get()),
+            signing_bonus: amount(signing_bonus.get()),
+            year: year.get().trim().parse().ok(),
+            location: location.get(),
+        };
+
+        spawn_local(async move {
+            match add_compensation_report(report).await {
+                Ok(Ok(())) => {
+                    set_status("Thanks! Your report is only ever shown combined with others.".to_string());
+                    set_pay("".to_string());
+                    set_housing_stipend("".to_string());
+                    set_relocation("".to_string());
+                    set_signing_bonus("".to_string());
+                    set_year("".to_string());
+                    set_location("".to_string());
+                    stats.refetch();
+                },
+                Ok(Err(e)) => set_status(e),
+                Err(e) => set_status("Failed to report compensation: ".to_string() + e.to_string().as_str()),
+            }
+        });
+    };
+
+    view! {
+        <div class="compensation">
+            <h2>"Compensation"</h2>
+            <Suspense fallback=move || view! { <p>"Loading compensation..."</p> }>
+                {move || stats.get().map(|stats| compensation_summary(&stats))}
+            </Suspense>
+            <Show
+                when=move || logged_in.get()
+                fallback=|| view! { <p>"Log in to report what you were paid."</p> }
+            >
+                <div class="compensation-form">
+                    <p>{status}</p>
+                    <div class="compensation-row">
+                        <input
+                            type="number"
+                            min="0"
+                            step="0.01"
+                            placeholder="Pay"
+                            on:input=move |ev| set_pay(event_target_value(&ev))
+                            prop:value=pay
+                        />
+                        <select on:change=move |ev| {
+                            let value = event_target_value(&ev);
+                            set_pay_period(PayPeriod::ALL.into_iter().find(|period| format!("{:?}", period) == value));
+                        }>
+                            {PayPeriod::ALL.into_iter().map(|option| view! {
+                                <option value=format!("{:?}", option) selected=move || pay_period.get() == Some(option)>{option.label()}</option>
+                            }).collect_view()}
+                        </select>
+                    </div>
+                    <input
+                        type="number"
+                        min="0"
+                        placeholder="Housing stipend per month (optional)"
+                        on:input=move |ev| set_housing_stipend(event_target_value(&ev))
+                        prop:value=housing_stipend
+                    />
+                    <input
+                        type="number"
+                        min="0"
+                        placeholder="Relocation (optional)"
+                        on:input=move |ev| set_relocation(event_target_value(&ev))
+                        prop:value=relocation
+                    />
+                    <input
+                        type="number"
+                        min="0"
+                        placeholder="Signing bonus (optional)"
+                        on:input=move |ev| set_signing_bonus(event_target_value(&ev))
+                        prop:value=signing_bonus
+                    />
+                    <div class="compensation-row">
+                        <input
+                            type="number"
+                            placeholder="Year"
+                            on:input=move |ev| set_year(event_target_value(&ev))
+                            prop:value=year
+                        />
+                        <input
+                            type="text"
+                            placeholder="City, State"
+                            on:input=move |ev| set_location(event_target_value(&ev))
+                            prop:value=location
+                        />
+                    </div>
+                    <button class="comment-submit" on:click=on_submit>"Report pay"</button>
+                </div>
+            </Show>
+        </div>
+    }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    fn report(pay: f64, year: i32, housing_stipend: Option<f64>) -> CompensationReport {
+        CompensationReport {
+            id: 0,
+            listing_id: 1,
+            author: "alice".to_string(),
+            timestamp: 0,
+            pay,
+            pay_period: PayPeriod::Hourly,
+            housing_stipend,
+            relocation: None,
+            signing_bonus: None,
+            year,
+            location: String::new(),
+        }
+    }
+
+    fn reports(pays: &[f64], year: i32) -> Vec<CompensationReport> {
+        pays.iter().map(|&pay| report(pay, year, None)).collect()
+    }
+
+    #[test]
+    fn too_few_reports_show_nothing() {
+        let stats = compensation_stats(reports(&[20.0, 30.0, 40.0, 50.0], 2023));
+        assert_eq!(stats, CompensationStats { reports: 4, ..Default::default() });
+    }
+
+    #[test]
+    fn the_fewest_reports_shown_cant_be_worked_back_to_their_values() {
+        // Everything but the median can change without changing what is published
+        let low = compensation_stats(reports(&[10.0, 20.0, 30.2, 31.0, 32.0], 2023));
+        let high = compensation_stats(reports(&[25.0, 29.0, 29.8, 60.0, 90.0], 2023));
+        assert_eq!(low.reports, MIN_REPORTS);
+        assert_eq!(low, high);
+        assert_eq!(low.hourly, Some(PayRange { median: 30.0, middle_half: None }));
+        assert_eq!(low.by_year, vec![]);
+    }
+
+    #[test]
+    fn the_middle_half_needs_more_reports() {
+        let pays: Vec<f64> = (1..=MIN_RANGE_REPORTS).map(|i| i as f64 * 10.0 + 0.4).collect();
+        let stats = compensation_stats(reports(&pays[1..], 2023));
+        assert_eq!(stats.hourly.unwrap().middle_half, None);
+
+        let stats = compensation_stats(reports(&pays, 2023));
+        assert_eq!(stats.hourly, Some(PayRange { median: 50.0, middle_half: Some((30.0, 80.0)) }));
+    }
+
+    #[test]
+    fn extras_need_more_than_the_minimum_of_their_own() {
+        let mut all: Vec<CompensationReport> = [20.0, 30.0, 40.0, 50.0, 60.0].map(|pay| report(pay, 2023, Some(pay * 50.0))).into();
+        all.push(report(70.0, 2023, None));
+        let stats = compensation_stats(all.clone());
+        assert!(stats.hourly.is_some());
+        assert_eq!(stats.housing_stipend, None);
+
+        all.push(report(80.0, 2023, Some(1234.4)));
+        assert_eq!(compensation_stats(all).housing_stipend, Some(1500.0));
+    }
+
+    #[test]
+    fn years_with_too_few_reports_are_left_out() {
+        let mut all = reports(&[20.0, 30.0, 40.0, 50.0, 60.0], 2022);
+        all.extend(reports(&[30.0, 40.0, 50.0, 60.0, 70.0, 80.0], 2023));
+        let stats = compensation_stats(all);
+        assert_eq!(stats.by_year, vec![YearlyPay { year: 2023, median: 50.0, reports: 6 }]);
+    }
+
+    #[test]
+    fn percentiles_use_the_nearest_reported_value() {
+        let sorted = [10.0, 20.0, 30.0, 40.4];
+        assert_eq!(percentile(&sorted, 0.0), 10.0);
+        assert_eq!(percentile(&sorted, 0.25), 10.0);
+        assert_eq!(percentile(&sorted, 0.5), 20.0);
+        assert_eq!(percentile(&sorted, 1.0), 40.0);
+        assert_eq!(percentile(&[7.0], 0.75), 7.0);
+    }
+}
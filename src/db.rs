@@ -3,18 +3,68 @@ use cfg_if::cfg_if;
 cfg_if! {
 
     if #[cfg(feature = "ssr")] {
-        use sqlx::Connection;
-		use sqlx::postgres::PgConnection;
+		use sqlx::postgres::{PgPool, PgPoolOptions};
+        use std::time::Duration;
 
         extern crate dotenv;
 
         use dotenv::dotenv;
         use std::env;
 
-        pub async fn db() -> Result<PgConnection, sqlx::Error> {
+        // Reads an optional numeric setting from the environment, falling back to a default
+        fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+            env::var(key).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+        }
+
+        pub fn database_url() -> String {
             dotenv().ok();
-            let pool = PgConnection::connect(&(std::env::var("DATABASE_URL").unwrap())).await?;
-            Ok(pool)
+            env::var("DATABASE_URL").expect("DATABASE_URL must be set")
+        }
+
+        // Builds the shared connection pool. Called once at startup from store.rs.
+        //
+        // DATABASE_MAX_CONNECTIONS and DATABASE_ACQUIRE_TIMEOUT (seconds) can be set to tune the pool.
+        pub async fn create_pool() -> Result<PgPool, sqlx::Error> {
+            PgPoolOptions::new()
+                .max_connections(env_or("DATABASE_MAX_CONNECTIONS", 10))
+                .acquire_timeout(Duration::from_secs(env_or("DATABASE_ACQUIRE_TIMEOUT", 30)))
+                .connect(&database_url())
+                .await
+        }
+
+        // Applies any pending migrations from the migrations directory, which is embedded at compile time
+        pub async fn run_migrations(pool: &PgPool) -> Result<(), sqlx::migrate::MigrateError> {
+            sqlx::migrate!("./migrations/postgres").run(pool).await
+        }
+
+        // Whether migrations should run on every startup. Set AUTO_MIGRATE=false to only run them with --migrate
+        pub fn auto_migrate() -> bool {
+            env_or("AUTO_MIGRATE", true)
+        }
+    }
+}
+
+cfg_if! {
+
+    if #[cfg(feature = "sqlite")] {
+        use std::str::FromStr;
+		use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+
+        // Same as create_pool, for a sqlite: DATABASE_URL. The database file is created if it doesn't exist.
+        pub async fn create_sqlite_pool() -> Result<SqlitePool, sqlx::Error> {
+            let options = SqliteConnectOptions::from_str(&database_url())?
+                .create_if_missing(true)
+                .foreign_keys(true);
+
+            SqlitePoolOptions::new()
+                .max_connections(env_or("DATABASE_MAX_CONNECTIONS", 10))
+                .acquire_timeout(Duration::from_secs(env_or("DATABASE_ACQUIRE_TIMEOUT", 30)))
+                .connect_with(options)
+                .await
+        }
+
+        pub async fn run_sqlite_migrations(pool: &SqlitePool) -> Result<(), sqlx::migrate::MigrateError> {
+            sqlx::migrate!("./migrations/sqlite").run(pool).await
         }
     }
 }
@@ -1,10 +1,30 @@
 use leptos::*;
-use crate::session::get_session;
+use crate::session::{get_session, set_school_only};
+use crate::school::School;
+use crate::role::Role;
 
 #[component]
-pub fn Header(profile_open: RwSignal<bool>, login_open: RwSignal<bool>) -> impl IntoView {
-    // Signal for the toggle switch
-    let (is_toggled, set_is_toggled) = create_signal(false);
+pub fn Header(
+    profile_open: RwSignal<bool>,
+    login_open: RwSignal<bool>,
+    // The logged in user's school, None for anonymous visitors
+    #[prop(into)]
+    school: Signal<Option<School>>,
+    // Whether only listings from the user's school are shown
+    school_only: RwSignal<bool>,
+    // The logged in user's role, User for anonymous visitors. Links to staff pages only show for those who can use them.
+    #[prop(into)]
+    role: Signal<Role>,
+) -> impl IntoView {
+    let on_toggle = move |_| {
+        let toggled = !school_only.get_untracked();
+        school_only.set(toggled);
+
+        // Remembered in the session so the choice survives reloads
+        spawn_local(async move {
+            set_school_only(toggled).await.ok();
+        });
+    };
 
     let on_profile = move |_| {
         spawn_local(async move {
@@ -26,14 +46,28 @@ pub fn Header(profile_open: RwSignal<bool>, login_open: RwSignal<bool>) -> impl
                 <h1>"Interniverse"</h1>
             </div>
             <div class="header-right">
-                // Slider/Toggle
-                <label class="switch">
-					<input type="checkbox" checked={is_toggled} on:input=move |_| set_is_toggled.update(|is_toggled| *is_toggled = !*is_toggled) />
-                    <span class="slider round"></span>
-                </label>
-                // School Name
-                // Replace "YourSchoolName" with the actual name of the school, but if is_toggled is false, then display "All Schools"
-				<span class="school-name">{ move || if is_toggled.get() { "YourSchoolName" } else { "All Schools" } }</span>
+                // Slider/Toggle, only for logged in users since anonymous visitors have no school
+                <Show
+                    when=move || school.with(|school| school.is_some())
+                    fallback=move || view! {
+                        <button class="school-login-btn" on:click=move |_| login_open.set(true)>"Log in to see your school"</button>
+                    }
+                >
+                    <label class="switch">
+                        <input type="checkbox" prop:checked=school_only on:input=on_toggle />
+                        <span class="slider round"></span>
+                    </label>
+                    // School Name
+                    <span class="school-name">
+                        { move || if school_only.get() { school.get().map(|school| school.name).unwrap_or_default() } else { "All Schools".to_string() } }
+                    </span>
+                </Show>
+                <Show when=move || role.get().includes(Role::Moderator) fallback=|| ()>
+                    <a class="header-link" href="/moderation">"Moderation"</a>
+                </Show>
+                <Show when=move || role.get().includes(Role::SchoolAdmin) fallback=|| ()>
+                    <a class="header-link" href="/admin">"Admin"</a>
+                </Show>
                 // Plus Icon for New Post
                 <a class="new-post-btn" href="/new-post">
 					<svg xmlns="http://www.w3.org/2000/svg" class="icon icon-tabler icon-tabler-circle-plus" width="44" height="44" viewBox="0 0 24 24" stroke-width="1.5" stroke="#ffffff" fill="none" stroke-linecap="round" stroke-linejoin="round">
@@ -0,0 +1,352 @@
+use leptos::*;
+use cfg_if::cfg_if;
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+
+cfg_if! {
+	if #[cfg(feature = "ssr")] {
+		use crate::store::store;
+		use crate::login::current_user;
+	}
+}
+
+// Most rounds a report can list
+pub const MAX_ROUNDS: i32 = 20;
+
+// How an interview process ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::Type))]
+#[cfg_attr(feature = "ssr", sqlx(type_name = "interview_outcome", rename_all = "lowercase"))]
+pub enum InterviewOutcome {
+    Offer,
+    Rejected,
+    Withdrew,
+    // Still waiting to hear back
+    Pending,
+}
+
+impl InterviewOutcome {
+    pub const ALL: [InterviewOutcome; 4] = [InterviewOutcome::Offer, InterviewOutcome::Rejected, InterviewOutcome::Withdrew, InterviewOutcome::Pending];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InterviewOutcome::Offer => "Got an offer",
+            InterviewOutcome::Rejected => "Rejected",
+            InterviewOutcome::Withdrew => "Withdrew",
+            InterviewOutcome::Pending => "Waiting to hear back",
+        }
+    }
+}
+
+// Someone's interview experience for a listing
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct InterviewReport {
+    pub id: i64,
+    pub listing_id: i64,
+    pub author: String,
+    // Milliseconds since the epoch, like comment timestamps
+    pub timestamp: i64,
+    pub rounds: i32,
+    // Which kinds of rounds the process had
+    pub online_assessment: bool,
+    pub phone_screen: bool,
+    pub onsite: bool,
+    pub questions: String,
+    // From 1 (easy) to 5 (hard)
+    pub difficulty: i32,
+    // Days from applying to hearing the outcome
+    pub process_days: Option<i32>,
+    pub outcome: InterviewOutcome,
+}
+
+impl InterviewReport {
+    // Labels for the kinds of rounds, e.g. ["OA", "Phone"]
+    pub fn round_types(&self) -> Vec<&'static str> {
+        [(self.online_assessment, "OA"), (self.phone_screen, "Phone"), (self.onsite, "Onsite")]
+            .into_iter()
+            .filter_map(|(had, label)| had.then_some(label))
+            .collect()
+    }
+}
+
+// An interview report as submitted through the form. The author and timestamp are filled in by the server.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NewInterviewReport {
+    pub listing_id: i64,
+    pub rounds: i32,
+    pub online_assessment: bool,
+    pub phone_screen: bool,
+    pub onsite: bool,
+    pub questions: String,
+    pub difficulty: i32,
+    pub process_days: Option<i32>,
+    pub outcome: Option<InterviewOutcome>,
+}
+
+impl NewInterviewReport {
+    pub fn validate(&self) -> Result<InterviewOutcome, String> {
+        if !(1..=MAX_ROUNDS).contains(&self.rounds) {
+            return Err(format!("Number of rounds must be between 1 and {}", MAX_ROUNDS));
+        }
+        if !(self.online_assessment || self.phone_screen || self.onsite) {
+            return Err("Choose at least one kind of round".to_string());
+        }
+        if !(1..=5).contains(&self.difficulty) {
+            return Err("Difficulty must be between 1 and 5".to_string());
+        }
+        if self.process_days.map_or(false, |days| days < 0) {
+            return Err("Timeline can't be negative".to_string());
+        }
+
+        self.outcome.ok_or_else(|| "Choose how the process ended".to_string())
+    }
+}
+
+// Interview reports summed up over all of a company's listings
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct InterviewStats {
+    pub reports: i64,
+    // Share of finished processes that ended in an offer. Pending ones don't count.
+    pub offer_rate: Option<f64>,
+    pub average_days: Option<f64>,
+    pub average_rounds: Option<f64>,
+    pub average_difficulty: Option<f64>,
+}
+
+#[server(GetInterviewReports, "/interview-reports")]
+pub async fn get_interview_reports(listing_id: i64) -> Result<Vec<InterviewReport>, ServerFnError> {
+    store()?.get_interview_reports(listing_id).await
+}
+
+#[server(GetInterviewStats, "/interview-stats")]
+pub async fn get_interview_stats(company_id: i64) -> Result<InterviewStats, ServerFnError> {
+    store()?.get_interview_stats(company_id).await
+}
+
+// Posts an interview report as the logged in user
+#[server(AddInterviewReport, "/add-interview-report")]
+pub async fn add_interview_report(report: NewInterviewReport) -> Result<Result<(), String>, ServerFnError> {
+    let Some(user) = current_user().await? else {
+        return Ok(Err("You must be logged in to share an interview".to_string()));
+    };
+
+    let outcome = match report.validate() {
+        Ok(outcome) => outcome,
+        Err(e) => return Ok(Err(e)),
+    };
+
+    if store()?.get_listing(report.listing_id).await?.is_none() {
+        return Ok(Err("This listing no longer exists".to_string()));
+    }
+
+    // The id is assigned by the store
+    store()?.add_interview_report(InterviewReport {
+        id: 0,
+        listing_id: report.listing_id,
+        author: user.name,
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        rounds: report.rounds,
+        online_assessment: report.online_assessment,
+        phone_screen: report.phone_screen,
+        onsite: report.onsite,
+        questions: report.questions.trim().to_string(),
+        difficulty: report.difficulty,
+        process_days: report.process_days,
+        outcome,
+    }).await?;
+    Ok(Ok(()))
+}
+
+// Like "Offer rate 40% · 21 days on average · 3.2 rounds · Difficulty 3.1/5"
+pub fn interview_stats_summary(stats: &InterviewStats) -> View {
+    if stats.reports == 0 {
+        return ().into_view();
+    }
+
+    let mut parts = Vec::new();
+    if let Some(offer_rate) = stats.offer_rate {
+        parts.push(format!("Offer rate {:.0}%", offer_rate * 100.0));
+    }
+    if let Some(days) = stats.average_days {
+        parts.push(format!("{:.0} days on average", days));
+    }
+    if let Some(rounds) = stats.average_rounds {
+        parts.push(format!("{:.1} rounds", rounds));
+    }
+    if let Some(difficulty) = stats.average_difficulty {
+        parts.push(format!("Difficulty {:.1}/5", difficulty));
+    }
+
+    view! {
+        <p class="interview-stats">
+            {parts.join(" · ")}
+            " (from "{stats.reports}{if stats.reports == 1 { " interview report)" } else { " interview reports)" }}
+        </p>
+    }.into_view()
+}
+
+#[component]
+fn InterviewReportView(report: InterviewReport) -> impl IntoView {
+    let mut details = vec![format!("{} {}", report.rounds, if report.rounds == 1 { "round" } else { "rounds" })];
+    details.push(report.round_types().join(", "));
+    details.push(format!("Difficulty {}/5", report.difficulty));
+    if let Some(days) = report.process_days {
+        details.push(format!("{} {}", days, if days == 1 { "day" } else { "days" }));
+    }
+
+    view! {
+        <div class="interview-report">
+            <div class="interview-report-header">
+                <span class="interview-report-author">{report.author.clone()}</span>
+                <span class="interview-report-outcome">{report.outcome.label()}</span>
+                <span class="comment-timestamp">
+                    {DateTime::from_timestamp(report.timestamp / 1000, 0).map(|timestamp| timestamp.format("%h %d %Y").to_string())}
+                </span>
+            </div>
+            <div class="interview-report-details">{details.join(" · ")}</div>
+            {(!report.questions.is_empty()).then(|| view! {
+                <div class="interview-report-questions">
+                    <b>"Questions: "</b>
+                    {report.questions.clone()}
+                </div>
+            })}
+        </div>
+    }
+}
+
+/// Renders the interview reports for a listing, with a form to add one when logged in
+#[component]
+pub fn InterviewReports(listing_id: i64, #[prop(into)] logged_in: Signal<bool>) -> impl IntoView {
+    let reports = create_resource(
+        move || listing_id,
+        |listing_id| async move { get_interview_reports(listing_id).await.unwrap_or_default() },
+    );
+
+    let (rounds, set_rounds) = create_signal("".to_string());
+    let (online_assessment, set_online_assessment) = create_signal(false);
+    let (phone_screen, set_phone_screen) = create_signal(false);
+    let (onsite, set_onsite) = create_signal(false);
+    let (questions, set_questions) = create_signal("".to_string());
+    let (difficulty, set_difficulty) = create_signal(3);
+    let (process_days, set_process_days) = create_signal("".to_string());
+    let (outcome, set_outcome) = create_signal(None::<InterviewOutcome>);
+    let (status, set_status) = create_signal("".to_string());
+
+    let on_submit = move |_| {
+        let report = NewInterviewReport {
+            listing_id,
+            rounds: rounds.get().trim().parse().unwrap_or(0),
+            online_assessment: online_assessment.get(),
+            phone_screen: phone_screen.get(),
+            onsite: onsite.get(),
+            questions: questions.get(),
+            difficulty: difficulty.get(),
+            process_days: process_days.get().trim().parse().ok(),
+            outcome: outcome.get(),
+        };
+
+        spawn_local(async move {
+            match add_interview_report(report).await {
+                Ok(Ok(())) => {
+                    set_status("".to_string());
+                    set_rounds("".to_string());
+                    set_online_assessment(false);
+                    set_phone_screen(false);
+                    set_onsite(false);
+                    set_questions("".to_string());
+                    set_difficulty(3);
+                    set_process_days("".to_string());
+                    set_outcome(None);
+                    reports.refetch();
+                },
+                Ok(Err(e)) => set_status(e),
+                Err(e) => set_status("Failed to share interview: ".to_string() + e.to_string().as_str()),
+            }
+        });
+    };
+
+    view! {
+        <div class="interview-reports">
+            <h2>"Interviews"</h2>
+            <Suspense fallback=move || view! { <p>"Loading interviews..."</p> }>
+                {move || reports.get().map(|reports| {
+                    if reports.is_empty() {
+                        return view! { <p>"No interviews shared yet"</p> }.into_view();
+                    }
+
+                    reports.into_iter().map(|report| view! { <InterviewReportView report=report/> }).collect_view()
+                })}
+            </Suspense>
+            <Show
+                when=move || logged_in.get()
+                fallback=|| view! { <p>"Log in to share your interview experience."</p> }
+            >
+                <div class="interview-form">
+                    <p>{status}</p>
+                    <label>
+                        "Rounds "
+                        <input
+                            type="number"
+                            min="1"
+                            max=MAX_ROUNDS
+                            on:input=move |ev| set_rounds(event_target_value(&ev))
+                            prop:value=rounds
+                        />
+                    </label>
+                    <div class="interview-round-types">
+                        <label>
+                            <input type="checkbox" prop:checked=online_assessment on:change=move |ev| set_online_assessment(event_target_checked(&ev))/>
+                            "Online assessment"
+                        </label>
+                        <label>
+                            <input type="checkbox" prop:checked=phone_screen on:change=move |ev| set_phone_screen(event_target_checked(&ev))/>
+                            "Phone"
+                        </label>
+                        <label>
+                            <input type="checkbox" prop:checked=onsite on:change=move |ev| set_onsite(event_target_checked(&ev))/>
+                            "Onsite"
+                        </label>
+                    </div>
+                    <textarea
+                        rows="4"
+                        placeholder="What were you asked?"
+                        on:input=move |ev| set_questions(event_target_value(&ev))
+                        prop:value=questions
+                    />
+                    <label>
+                        "Difficulty "
+                        <select on:change=move |ev| set_difficulty(event_target_value(&ev).parse().unwrap_or(3))>
+                            {(1..=5).map(|value| view! {
+                                <option value=value selected=move || difficulty.get() == value>{value}</option>
+                            }).collect_view()}
+                        </select>
+                    </label>
+                    <label>
+                        "Days from applying to hearing back "
+                        <input
+                            type="number"
+                            min="0"
+                            on:input=move |ev| set_process_days(event_target_value(&ev))
+                            prop:value=process_days
+                        />
+                    </label>
+                    <label>
+                        "Outcome "
+                        <select on:change=move |ev| {
+                            let value = event_target_value(&ev);
+                            set_outcome(InterviewOutcome::ALL.into_iter().find(|outcome| format!("{:?}", outcome) == value));
+                        }>
+                            <option value="" selected=move || outcome.get().is_none()>"Choose..."</option>
+                            {InterviewOutcome::ALL.into_iter().map(|option| view! {
+                                <option value=format!("{:?}", option) selected=move || outcome.get() == Some(option)>{option.label()}</option>
+                            }).collect_view()}
+                        </select>
+                    </label>
+                    <button class="comment-submit" on:click=on_submit>"Share interview"</button>
+                </div>
+            </Show>
+        </div>
+    }
+}
@@ -1,8 +1,17 @@
 pub mod app;
 pub mod db;
+pub mod store;
+pub mod mailer;
 pub mod popup;
 pub mod listing;
+pub mod company;
 pub mod comment;
+pub mod rating;
+pub mod interview;
+pub mod compensation;
+pub mod moderation;
+pub mod role;
+pub mod admin;
 use cfg_if::cfg_if;
 pub mod header;
 pub mod search_bar;
@@ -11,6 +20,9 @@ pub mod login;
 pub mod registration;
 pub mod session;
 pub mod profile;
+pub mod new_post;
+pub mod school;
+pub mod verify_email;
 
 cfg_if! {
 if #[cfg(feature = "hydrate")] {
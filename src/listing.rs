@@ -1,61 +1,137 @@
 use cfg_if::cfg_if;
-use crate::comment::{Comment, get_comments, self};
+use crate::comment::{Comment, CommentSort, CommentThread, DimensionRatings, RatingInput, dimension_breakdown, get_comments, add_comment, self};
+use crate::profile::get_profile;
+use crate::rating::{get_rating_summary, rating_histogram, RatingSummary};
+use crate::interview::InterviewReports;
+use crate::compensation::CompensationSection;
+use crate::moderation::{ReportButton, ReportTarget};
+use crate::registration::Registration;
 use crate::popup::Popup;
 use serde::{Deserialize, Serialize};
 use crate::{login::*, listing};
 use chrono::prelude::*;
 use leptos::*;
 use leptos::{ev::SubmitEvent, *};
-use web_sys::window;
+use leptos_meta::Title;
+use leptos_router::*;
+use crate::app::NotFound;
+use crate::school::School;
+use crate::company::Company;
 
 cfg_if! {
 	if #[cfg(feature = "ssr")] {
-		use crate::db::db;
+		use crate::store::store;
+		use crate::role::{require_role, Role};
+
+		// One page of listings in the given order, with a cursor for the next page if there is one
+		async fn listing_batch(filters: &ListingFilters, sort: ListingSort, cursor: Option<ListingCursor>) -> Result<ListingBatch, ServerFnError> {
+			// One extra row tells us whether there is another page
+			let mut rows = store()?.list_listings(filters, sort, cursor.as_ref(), LISTINGS_PER_PAGE + 1).await?;
+			let has_more = rows.len() as i64 > LISTINGS_PER_PAGE;
+			rows.truncate(LISTINGS_PER_PAGE as usize);
+
+			let next_cursor = if has_more { rows.last().map(|(_, cursor)| cursor.clone()) } else { None };
+
+			Ok(ListingBatch {
+				listings: rows.into_iter().map(|(listing, _)| listing).collect(),
+				next_cursor,
+			})
+		}
     }
 }
 
-fn get_id_from_url() -> Option<i64> {
-    if let Some(window) = window() {
-        if let Some(location) = window.location().href().ok() {
-            // Extract the ID from the URL.
-            // Assuming the URL is something like 'http://example.com/postpage/123'
-            let parts: Vec<&str> = location.split('/').collect();
-            if parts.len() > 1 {
-                return parts.last()?.parse::<i64>().ok();
-            }
+// Whether the internship is done from home, the office, or a mix of both
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::Type))]
+#[cfg_attr(feature = "ssr", sqlx(type_name = "work_mode", rename_all = "lowercase"))]
+pub enum WorkMode {
+    Remote,
+    Hybrid,
+    Onsite,
+}
+
+impl WorkMode {
+    pub const ALL: [WorkMode; 3] = [WorkMode::Remote, WorkMode::Hybrid, WorkMode::Onsite];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WorkMode::Remote => "Remote",
+            WorkMode::Hybrid => "Hybrid",
+            WorkMode::Onsite => "On-site",
+        }
+    }
+}
+
+// The term an internship runs in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::Type))]
+#[cfg_attr(feature = "ssr", sqlx(type_name = "season", rename_all = "lowercase"))]
+pub enum Season {
+    Spring,
+    Summer,
+    Fall,
+    Winter,
+}
+
+impl Season {
+    pub const ALL: [Season; 4] = [Season::Spring, Season::Summer, Season::Fall, Season::Winter];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Season::Spring => "Spring",
+            Season::Summer => "Summer",
+            Season::Fall => "Fall",
+            Season::Winter => "Winter",
         }
     }
-    None
 }
 
 // Listing contains information for a company's internship listing along with a list of comments
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+//
+// The structured fields are optional because listings posted before they existed don't have them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
 pub struct Listing {
-    company: String,
-    position: String,
-    description: String,
-    url: String,
-    id: i64,
-    school: String,
+    // The company's canonical name, copied from the companies table so it can be searched and sorted by
+    pub(crate) company: String,
+    pub(crate) company_id: i64,
+    pub(crate) position: String,
+    pub(crate) description: String,
+    pub(crate) url: String,
+    pub(crate) id: i64,
+    // The school's canonical name, copied from the schools table so it can be searched
+    pub(crate) school: String,
+    pub(crate) school_id: i64,
+    pub(crate) location: String,
+    pub(crate) work_mode: Option<WorkMode>,
+    pub(crate) paid: Option<bool>,
+    pub(crate) hourly_rate: Option<f64>,
+    pub(crate) season: Option<Season>,
+    pub(crate) year: Option<i32>,
+    // Application deadline in milliseconds since the epoch, like comment timestamps
+    pub(crate) deadline: Option<i64>,
+    // Average comment rating from 0 to 1 and the number of comments, stored when comments are added
+    pub(crate) rating: f64,
+    pub(crate) reviews: i64,
+    // The rating pulled towards the prior like RatingSummary::score, which listings are sorted by
+    pub(crate) score: f64,
+    // Hidden by a moderator. Hidden listings are left out of browsing and search, and only moderators can open them.
+    pub(crate) hidden: bool,
+    // The account that posted the listing, None for listings older than this. Never sent to the client.
+    #[serde(skip)]
+    pub(crate) user_id: Option<i32>,
 }
 
 // Implementation of getters for comment data
 impl Listing {
-    pub fn new(company: String, position: String, description: String, url: String, id: i64, school: String) -> Self {
-        Listing {
-            company,
-            position,
-            description,
-            url,
-            id,
-            school,
-        }
-    }
-
     pub fn get_company(&self) -> &String {
         &self.company
     }
 
+    pub fn get_company_id(&self) -> i64 {
+        self.company_id
+    }
+
     pub fn get_position(&self) -> &String {
         &self.position
     }
@@ -75,117 +151,442 @@ impl Listing {
     pub fn get_school(&self) -> &String {
         &self.school
     }
+
+    pub fn get_school_id(&self) -> i64 {
+        self.school_id
+    }
+
+    pub fn get_location(&self) -> &String {
+        &self.location
+    }
+
+    pub fn get_work_mode(&self) -> Option<WorkMode> {
+        self.work_mode
+    }
+
+    pub fn get_paid(&self) -> Option<bool> {
+        self.paid
+    }
+
+    pub fn get_hourly_rate(&self) -> Option<f64> {
+        self.hourly_rate
+    }
+
+    pub fn get_season(&self) -> Option<Season> {
+        self.season
+    }
+
+    pub fn get_year(&self) -> Option<i32> {
+        self.year
+    }
+
+    pub fn get_deadline(&self) -> Option<i64> {
+        self.deadline
+    }
+
+    pub fn get_rating(&self) -> f64 {
+        self.rating
+    }
+
+    pub fn get_reviews(&self) -> i64 {
+        self.reviews
+    }
+
+    // The sort keys to continue a page of listings after this one
+    pub fn get_cursor(&self) -> ListingCursor {
+        ListingCursor {
+            id: self.id,
+            score: self.score,
+            reviews: self.reviews,
+            company: self.company.clone(),
+        }
+    }
+
+    // Short labels for the structured fields that are filled in, e.g. ["Remote", "$25/hr", "Summer 2024"]
+    pub fn get_tags(&self) -> Vec<String> {
+        let mut tags = Vec::new();
+
+        if !self.location.is_empty() {
+            tags.push(self.location.clone());
+        }
+        if let Some(work_mode) = self.work_mode {
+            tags.push(work_mode.label().to_string());
+        }
+        match (self.paid, self.hourly_rate) {
+            (Some(true), Some(rate)) => tags.push(format!("${:.2}/hr", rate)),
+            (Some(true), None) => tags.push("Paid".to_string()),
+            (Some(false), _) => tags.push("Unpaid".to_string()),
+            (None, _) => (),
+        }
+        match (self.season, self.year) {
+            (Some(season), Some(year)) => tags.push(format!("{} {}", season.label(), year)),
+            (Some(season), None) => tags.push(season.label().to_string()),
+            (None, Some(year)) => tags.push(year.to_string()),
+            (None, None) => (),
+        }
+
+        tags
+    }
+
+    // The deadline as a date, e.g. "Jan 15 2024"
+    pub fn get_deadline_text(&self) -> Option<String> {
+        self.deadline
+            .and_then(|deadline| DateTime::from_timestamp(deadline / 1000, 0))
+            .map(|deadline| deadline.format("%h %d %Y").to_string())
+    }
+}
+
+// A listing as submitted through the new post form. The id is assigned by the database.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NewListing {
+    pub company: String,
+    pub position: String,
+    pub description: String,
+    pub url: String,
+    pub school_id: Option<i64>,
+    pub location: String,
+    pub work_mode: Option<WorkMode>,
+    pub paid: Option<bool>,
+    pub hourly_rate: Option<f64>,
+    pub season: Option<Season>,
+    pub year: Option<i32>,
+    pub deadline: Option<i64>,
+}
+
+impl NewListing {
+    pub fn into_listing(self, id: i64, school: School, company: Company) -> Listing {
+        Listing {
+            company: company.name,
+            company_id: company.id,
+            position: self.position,
+            description: self.description,
+            url: self.url,
+            id,
+            school: school.name,
+            school_id: school.id,
+            location: self.location,
+            work_mode: self.work_mode,
+            paid: self.paid,
+            hourly_rate: self.hourly_rate,
+            season: self.season,
+            year: self.year,
+            deadline: self.deadline,
+            rating: 0.0,
+            reviews: 0,
+            score: RatingSummary::new(0.0, [0; 5]).score,
+            hidden: false,
+            user_id: None,
+        }
+    }
+}
+
+// Validation errors for a new listing, one per form field
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListingErrors {
+    pub company: Option<String>,
+    pub position: Option<String>,
+    pub description: Option<String>,
+    pub url: Option<String>,
+    pub school: Option<String>,
+    pub location: Option<String>,
+    pub work_mode: Option<String>,
+    pub pay: Option<String>,
+    pub season: Option<String>,
+    // Problems with who is posting rather than with a field
+    pub author: Option<String>,
+}
+
+impl ListingErrors {
+    pub fn is_empty(&self) -> bool {
+        *self == ListingErrors::default()
+    }
+}
+
+impl NewListing {
+    // Checks every field, so the form can show all problems at once
+    pub fn validate(&self) -> ListingErrors {
+        fn required(value: &str, name: &str, max_len: usize) -> Option<String> {
+            if value.trim().is_empty() {
+                Some(format!("{} is required", name))
+            } else if value.len() > max_len {
+                Some(format!("{} must be at most {} characters", name, max_len))
+            } else {
+                None
+            }
+        }
+
+        let url = self.url.trim();
+
+        ListingErrors {
+            company: required(&self.company, "Company", 100),
+            position: required(&self.position, "Position", 100),
+            description: required(&self.description, "Description", 5000),
+            url: required(url, "URL", 2000).or_else(|| {
+                if url.starts_with("http://") || url.starts_with("https://") {
+                    None
+                } else {
+                    Some("URL must start with http:// or https://".to_string())
+                }
+            }),
+            school: self.school_id.is_none().then(|| "Choose a school from the list".to_string()),
+            location: required(&self.location, "Location", 100),
+            work_mode: self.work_mode.is_none().then(|| "Choose remote, hybrid or on-site".to_string()),
+            pay: match (self.paid, self.hourly_rate) {
+                (None, _) => Some("Choose paid or unpaid".to_string()),
+                (Some(false), Some(_)) => Some("Unpaid internships can't have an hourly rate".to_string()),
+                (Some(true), Some(rate)) if !(rate > 0.0 && rate < 1000.0) => Some("Hourly rate must be between $0 and $1000".to_string()),
+                _ => None,
+            },
+            season: match (self.season, self.year) {
+                (None, _) | (_, None) => Some("Season and year are required".to_string()),
+                (_, Some(year)) if !(2000..=2100).contains(&year) => Some("Year must be between 2000 and 2100".to_string()),
+                _ => None,
+            },
+            // Checked by add_listing, which knows who is posting
+            author: None,
+        }
+    }
+}
+
+// Number of listings returned per page of search results
+pub const LISTINGS_PER_PAGE: i64 = 20;
+
+// Narrows down listings. Empty fields don't filter anything.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListingFilters {
+    pub school_id: Option<i64>,
+    pub company_id: Option<i64>,
+    // Matches any part of the location, ignoring case
+    pub location: Option<String>,
+    pub work_mode: Option<WorkMode>,
+    pub paid: Option<bool>,
+    pub season: Option<Season>,
+    pub year: Option<i32>,
+}
+
+impl ListingFilters {
+    // Same rules as the SQL stores apply, for filtering in memory
+    pub fn matches(&self, listing: &Listing) -> bool {
+        self.school_id.map_or(true, |school_id| listing.school_id == school_id)
+            && self.company_id.map_or(true, |company_id| listing.company_id == company_id)
+            && self.location.as_ref().map_or(true, |location| listing.location.to_lowercase().contains(&location.to_lowercase()))
+            && self.work_mode.map_or(true, |work_mode| listing.work_mode == Some(work_mode))
+            && self.paid.map_or(true, |paid| listing.paid == Some(paid))
+            && self.season.map_or(true, |season| listing.season == Some(season))
+            && self.year.map_or(true, |year| listing.year == Some(year))
+    }
+}
+
+// One page of search results
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ListingResults {
+    pub listings: Vec<Listing>,
+    pub has_more: bool,
+}
+
+// Orders for browsing listings
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ListingSort {
+    #[default]
+    Newest,
+    HighestRated,
+    MostReviewed,
+    Company,
+}
+
+impl ListingSort {
+    pub const ALL: [ListingSort; 4] = [ListingSort::Newest, ListingSort::HighestRated, ListingSort::MostReviewed, ListingSort::Company];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ListingSort::Newest => "Newest",
+            ListingSort::HighestRated => "Highest rated",
+            ListingSort::MostReviewed => "Most reviewed",
+            ListingSort::Company => "Company (A-Z)",
+        }
+    }
+}
+
+// The sort keys of the last listing on a page. Passed back to continue from where that page ended.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ListingCursor {
+    pub id: i64,
+    pub score: f64,
+    pub reviews: i64,
+    pub company: String,
+}
+
+// One page of listings, with the cursor for the next page if there is one
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ListingBatch {
+    pub listings: Vec<Listing>,
+    pub next_cursor: Option<ListingCursor>,
 }
 
 #[server(GetListings, "/listings")]
-pub async fn get_listings(school: String) -> Result<Result<Vec<Listing>, String>, ServerFnError> {
-    let mut conn = db().await?;
-    let listings = sqlx::query_as!(Listing, "SELECT * FROM listings WHERE school = $1", school)
-        .fetch_all(&mut conn).await?;
+pub async fn get_listings(school_id: i64, filters: ListingFilters, sort: ListingSort, cursor: Option<ListingCursor>) -> Result<Result<ListingBatch, String>, ServerFnError> {
+    let filters = ListingFilters { school_id: Some(school_id), ..filters };
+    let listings = listing_batch(&filters, sort, cursor).await?;
     Ok(Ok(listings))
 }
 
 #[server(GetAllListings, "/all-listings")]
-pub async fn get_all_listings() -> Result<Vec<Listing>, ServerFnError> {
-    let mut conn = db().await?;
-    let listings = sqlx::query_as!(Listing, "SELECT * FROM listings")
-        .fetch_all(&mut conn).await?;
-    Ok(listings)
+pub async fn get_all_listings(filters: ListingFilters, sort: ListingSort, cursor: Option<ListingCursor>) -> Result<ListingBatch, ServerFnError> {
+    listing_batch(&filters, sort, cursor).await
+}
+
+// Full-text search over company, position, description and school, best matches first.
+// An empty query returns every listing matching the filters, newest first.
+#[server(SearchListings, "/search-listings")]
+pub async fn search_listings(query: String, filters: ListingFilters, page: u32) -> Result<ListingResults, ServerFnError> {
+    // One extra row tells us whether there is another page
+    let mut listings = store()?.search_listings(query.trim(), &filters, LISTINGS_PER_PAGE + 1, page as i64 * LISTINGS_PER_PAGE).await?;
+    let has_more = listings.len() as i64 > LISTINGS_PER_PAGE;
+    listings.truncate(LISTINGS_PER_PAGE as usize);
+
+    Ok(ListingResults { listings, has_more })
+}
+
+// Average of each rating dimension over the listing's comments
+#[server(GetListingRatings, "/listing-ratings")]
+pub async fn get_listing_ratings(listing_id: i64) -> Result<DimensionRatings, ServerFnError> {
+    store()?.get_dimension_averages(listing_id).await
 }
 
 #[server(GetListing, "/server")]
 pub async fn get_listing(id: i64) -> Result<Option<Listing>, ServerFnError> {
-    let mut conn = db().await?;
+    let listing = store()?.get_listing(id).await?;
 
-    // Perform a query that selects a listing by ID. Adjust SQL as needed.
-    let result = sqlx::query_as!(Listing, "SELECT * FROM listings WHERE id = $1", id)
-        .fetch_one(&mut conn).await?;
-    Ok(result)
+    // Hidden listings are only there for moderators
+    if listing.as_ref().map_or(false, |listing| listing.hidden) && require_role(Role::Moderator).await?.is_none() {
+        return Ok(None);
+    }
+    Ok(listing)
 }
 
+// Adds a listing and returns its id, or the validation errors if any field is invalid.
+// Only users with a verified school email can post.
 #[server(AddListing, "/add-listing")]
-pub async fn add_listing(listing: Listing) -> Result<Result<(), String>, ServerFnError> {
-    let mut conn = db().await?;
-    let rows = sqlx::query!("INSERT INTO listings (company, position, description, url, id, school) VALUES ($1, $2, $3, $4, $5, $6)",
-        listing.company, listing.position, listing.description, listing.url, listing.id, listing.school)
-        .execute(&mut conn).await?;
-    Ok(Ok(()))
+pub async fn add_listing(listing: NewListing) -> Result<Result<i64, ListingErrors>, ServerFnError> {
+    let mut errors = listing.validate();
+
+    let user = current_user().await?;
+    errors.author = match &user {
+        None => Some("You must be logged in to post a listing".to_string()),
+        Some(user) if !user.verified => Some("Verify your school email from your profile to post listings".to_string()),
+        Some(_) => None,
+    };
+
+    if let Some(school_id) = listing.school_id {
+        if store()?.get_school(school_id).await?.is_none() {
+            errors.school = Some("Unknown school".to_string());
+        }
+    }
+
+    if !errors.is_empty() {
+        return Ok(Err(errors));
+    }
+
+    // Without a user errors.author would be set
+    let Some(user) = user else {
+        return Ok(Err(errors));
+    };
+
+    // Listings for the same company under a different case or an alias share one company
+    let company = store()?.find_or_create_company(listing.company.trim()).await?;
+
+    let id = store()?.add_listing(listing, company.id, user.id).await?;
+    Ok(Ok(id))
 }
 
 // Renders a navbar structure
 #[component]
 pub fn Listing(listing_data: ReadSignal<Listing>) -> impl IntoView {
     use leptos::html::Input;
-    let mut next_id = 4;
-    // Create initial list and store as a signal
-    let (comments, set_comments) = create_signal(vec![
-        Comment::new(
-            String::from("Bob"),
-            String::from("Hi. My name is bob. I don't know how to tell you this, but I am the author of the bible. Yep. I am jessus the lord and savior. Just kidding, I'm no god, but Jesus christ is my ni-"),
-            Utc::now().timestamp_millis(),
-            0.65,
-            1,
-        ),
-        Comment::new(
-            String::from("John"),
-            String::from("Hi. My name is John. I am a bot."),
-            Utc::now().timestamp_millis(),
-            0.8,
-            2,
-        ),
-        Comment::new(
-            String::from("Jane"),
-            String::from("I hate this job"),
-            Utc::now().timestamp_millis(),
-            0.2,
-            3,
-        ),
-    ] as Vec<Comment>);
-    // Average rating, precalled to the above list of sample comments
-    let (avg_rating, set_avg_rating) = create_signal(get_avg_rating(&comments.get()));
+    // Comments are loaded from the database, sorted there, and refetched after posting or voting
+    let (comment_sort, set_comment_sort) = create_signal(CommentSort::default());
+    let comments = create_resource(
+        move || (listing_data.get().get_id(), comment_sort.get()),
+        |(listing_id, sort)| async move { get_comments(listing_id, sort).await.unwrap_or_default() },
+    );
+    let summary = create_resource(
+        move || listing_data.get().get_id(),
+        |listing_id| async move { get_rating_summary(listing_id).await.ok().flatten().unwrap_or_default() },
+    );
+    let avg_rating = move || summary.get().map(|summary| summary.average).unwrap_or(0.0);
+    let dimension_averages = create_resource(
+        move || listing_data.get().get_id(),
+        |listing_id| async move { get_listing_ratings(listing_id).await.unwrap_or_default() },
+    );
+
+    // Only logged in users can comment, everyone else gets a login prompt
+    let profile = create_resource(|| (), |_| async move { get_profile().await.ok().flatten() });
+    let login_open = create_rw_signal(false);
+    let register_open = create_rw_signal(false);
+    let reload_profile = create_rw_signal(false);
+
+    create_effect(move |_| {
+        if reload_profile.get() {
+            profile.refetch();
+            // Votes shown on comments depend on who is logged in
+            comments.refetch();
+
+            reload_profile.set(false);
+        }
+    });
+
+    // Posting again updates the viewer's review instead of adding another
+    let has_review = move || comments.with(|comments| comments.iter().flatten().any(|comment| comment.is_own() && !comment.is_reply()));
+
+    // Set when a comment is edited or deleted from its thread
+    let reload_ratings = create_rw_signal(false);
+
+    create_effect(move |_| {
+        if reload_ratings.get() {
+            summary.refetch();
+            dimension_averages.refetch();
+
+            reload_ratings.set(false);
+        }
+    });
+
     // Writing Comments Signals
     let (input_content, set_input_content) =
         create_signal(String::from(""));
     let input_element: NodeRef<Input> = create_node_ref();
     let (star_input, set_star_input) = create_signal(4);
+    // One optional star rating per dimension, in the order of DimensionRatings::LABELS
+    let dimension_inputs = DimensionRatings::LABELS.map(|_| create_rw_signal(None::<u8>));
+    let (anonymous, set_anonymous) = create_signal(false);
+    let (status, set_status) = create_signal(String::from(""));
 
     // On button click, make sure a comment is written and signed in
     let on_submit = move |ev: SubmitEvent| {
         ev.prevent_default();
         let value = input_element().expect("<input> to exist").value();
-        // If our string is good we add it as follows:
-        set_input_content(value);
-        // Add Value
-        let new_comment = Comment::new(
-            String::from("Guest"),
-            input_content.get(),
-            Utc::now().timestamp_millis(),
-            star_input.get() as f64 / 5.0,
-            next_id,
-        );
-        // Add the comment to the list of comments
-        set_comments.update(move |comments| {
-            comments.push(new_comment);
-        });
-
-        set_avg_rating.update(move |val| {
-            *val = get_avg_rating(&comments.get());
+        let rating = star_input.get_untracked() as f64 / 5.0;
+        let dimensions = DimensionRatings::from_values(dimension_inputs.map(|input| input.get_untracked().map(|stars| stars as f64 / 5.0)));
+        let listing_id = listing_data.get_untracked().get_id();
+        let anonymous = anonymous.get_untracked();
+
+        spawn_local(async move {
+            match add_comment(value, rating, dimensions, listing_id, anonymous).await {
+                Ok(Ok(())) => {
+                    set_input_content(String::from(""));
+                    set_status(String::from(""));
+                    for input in dimension_inputs {
+                        input.set(None);
+                    }
+                    comments.refetch();
+                    summary.refetch();
+                    dimension_averages.refetch();
+                },
+                Ok(Err(e)) => set_status(e),
+                Err(e) => set_status("Failed to post comment: ".to_string() + e.to_string().as_str()),
+            }
         });
-        next_id += 1;
     };
 
-    // Gets an average rating given a list of comments
-    fn get_avg_rating(comment_list: &Vec<Comment>) -> f64 {
-        let mut total_rating = 0.0;
-        let mut count = 0.0;
-        for c in comment_list.iter() {
-            count += 1.0;
-            total_rating += c.get_rating();
-        }
-
-        total_rating / count
-    }
-
     // Generates stars based on the value of progress
     fn generate_star_avg(filled_stars: f64) -> Vec<impl IntoView> {
         (1..=5).map(|i| {
@@ -383,15 +784,15 @@ pub fn Listing(listing_data: ReadSignal<Listing>) -> impl IntoView {
         <div class="listing">
             <div class="listing-main">
                 <div class="listing-header">
-                    <div class="listing-company">
+                    <a class="listing-company" href=format!("/company/{}", listing_data.get().get_company_id())>
                         {listing_data.get().get_company()}
-                    </div>
+                    </a>
                     <div class="star-rating">
                         <div class="stars">
-                            {move || generate_star_avg(avg_rating.get() * 5.0)}
+                            {move || generate_star_avg(avg_rating() * 5.0)}
                         </div>
                         <div class="star-count">
-                            {move || (avg_rating.get()*500.0).round()/100.0}
+                            {move || (avg_rating()*500.0).round()/100.0}
                         </div>
                     </div>
                     <a class ="listing-url" href={listing_data.get().get_url()} target="_blank">
@@ -406,59 +807,158 @@ pub fn Listing(listing_data: ReadSignal<Listing>) -> impl IntoView {
                 <div class ="listing-position">
                     {listing_data.get().get_position()}
                 </div>
+                <Suspense fallback=|| ()>
+                    {move || summary.get().map(|summary| view! {
+                        <p class="listing-review-count">{summary.label()}</p>
+                        {rating_histogram(&summary)}
+                    })}
+                    {move || dimension_averages.get().map(dimension_breakdown)}
+                </Suspense>
+                <div class="listing-tags">
+                    {listing_data.get().get_tags().into_iter().map(|tag| view! { <span class="listing-tag">{tag}</span> }).collect_view()}
+                    {listing_data.get().get_deadline_text().map(|deadline| view! { <span class="listing-tag">"Apply by " {deadline}</span> })}
+                </div>
                 <div class ="listing-description">
                         {listing_data.get().get_description()}
                 </div>
+                <ReportButton
+                    target=ReportTarget::Listing(listing_data.get().get_id())
+                    logged_in=Signal::derive(move || profile.get().flatten().is_some())
+                />
             </div>
             <div class="comment-container">
-                <For
-                each = comments
-                key = |c| c.get_listing_id()
-                children=move |c: Comment| {
-                    view! {
-                        <div class="comment-shell">
-                            <Comment comment_data=c />
-                        </div>
+                <div class="comment-sort">
+                    <label for="comment-sort"><b>"Sort by "</b></label>
+                    <select
+                        name="comment-sort"
+                        on:change=move |ev| {
+                            let value = event_target_value(&ev);
+                            if let Some(option) = CommentSort::ALL.into_iter().find(|option| format!("{:?}", option) == value) {
+                                set_comment_sort(option);
+                            }
+                        }
+                    >
+                        {CommentSort::ALL.into_iter().map(|option| view! {
+                            <option value=format!("{:?}", option) selected=move || comment_sort.get() == option>{option.label()}</option>
+                        }).collect_view()}
+                    </select>
+                </div>
+                <Suspense fallback=move || view! { <p>"Loading comments..."</p> }>
+                    <For
+                    each = move || comments.get().unwrap_or_default().into_iter().filter(|c| !c.is_reply())
+                    key = |c| c.get_id()
+                    children=move |c: Comment| {
+                        view! {
+                            <div class="comment-shell">
+                                <CommentThread
+                                    comment=c
+                                    comments=comments
+                                    depth=0
+                                    viewer=Signal::derive(move || profile.get().flatten())
+                                    reload_ratings=reload_ratings
+                                />
+                            </div>
+                        }
                     }
-                }
-                />
+                    />
+                </Suspense>
             </div>
 
-            <form class="comment-form" on:submit=on_submit>
-                <input class="comment-box" type="text"
-                value = input_content
-                placeholder = "Write your comment here."
-                node_ref=input_element
-                />
-                <div class="star-rating">
-                    <div class="stars">
-                        <div type="button" on:click=on_star_click1 inner_html={star_1}/>
-                        <div type="button" on:click=on_star_click2 inner_html={star_2}/>
-                        <div type="button" on:click=on_star_click3 inner_html={star_3}/>
-                        <div type="button" on:click=on_star_click4 inner_html={star_4}/>
-                        <div type="button" on:click=on_star_click5 inner_html={star_5}/>
-                    </div>
-                </div>
-                <input class="comment-submit" type="submit" value="Submit"/>
-            </form>
+            <Suspense fallback=|| ()>
+                <Show
+                    when=move || profile.get().flatten().is_some()
+                    fallback=move || view! {
+                        <div class="comment-login-prompt">
+                            <p>"Log in to leave a review."</p>
+                            <button class="login-button" on:click=move |_| login_open.set(true)>"Log in"</button>
+                        </div>
+                    }
+                >
+                    <p class="comment-status">{status}</p>
+                    <form class="comment-form" on:submit=on_submit>
+                        <input class="comment-box" type="text"
+                        prop:value = input_content
+                        on:input=move |ev| set_input_content(event_target_value(&ev))
+                        placeholder = "Write your comment here."
+                        node_ref=input_element
+                        />
+                        <div class="star-rating">
+                            <div class="stars">
+                                <div type="button" on:click=on_star_click1 inner_html={star_1}/>
+                                <div type="button" on:click=on_star_click2 inner_html={star_2}/>
+                                <div type="button" on:click=on_star_click3 inner_html={star_3}/>
+                                <div type="button" on:click=on_star_click4 inner_html={star_4}/>
+                                <div type="button" on:click=on_star_click5 inner_html={star_5}/>
+                            </div>
+                        </div>
+                        <div class="rating-inputs">
+                            {DimensionRatings::LABELS.into_iter().zip(dimension_inputs).map(|(label, value)| view! {
+                                <RatingInput label=label value=value/>
+                            }).collect_view()}
+                        </div>
+                        // The school is shown, the name isn't
+                        <label class="comment-anonymous">
+                            <input type="checkbox"
+                                prop:checked=anonymous
+                                on:change=move |ev| set_anonymous(event_target_checked(&ev))
+                            />
+                            "Post anonymously"
+                        </label>
+                        <input class="comment-submit" type="submit" value=move || if has_review() { "Update review" } else { "Submit" }/>
+                    </form>
+                </Show>
+            </Suspense>
+            <CompensationSection
+                listing_id=listing_data.get_untracked().get_id()
+                logged_in=Signal::derive(move || profile.get().flatten().is_some())
+            />
+            <InterviewReports
+                listing_id=listing_data.get_untracked().get_id()
+                logged_in=Signal::derive(move || profile.get().flatten().is_some())
+            />
         </div>
+        <Login open=login_open reload_profile=reload_profile register_open=register_open/>
+        <Registration open=register_open reload_profile=reload_profile login_open=login_open/>
     }
 }
 
+// Route params for /listing/:id
+#[derive(Params, Clone, Debug, PartialEq)]
+struct ListingParams {
+    id: i64,
+}
+
+// Page for a single listing. The route renders with SsrMode::Async so an unknown id can respond with a 404.
+#[component]
 pub fn ListingPage() -> impl IntoView {
-    let id = get_id_from_url().unwrap_or(1); // If ID is not found, default to 1.
+    let params = use_params::<ListingParams>();
+    let id = move || params.with(|params| params.as_ref().map(|params| params.id).ok());
 
-    // Create a resource that will fetch the listing data asynchronously.
     let listing_resource = create_resource(
-        || (),
-		|_| async move { get_listing(id).await },
+        id,
+        |id| async move {
+            match id {
+                Some(id) => get_listing(id).await,
+                None => Ok(None),
+            }
+        },
     );
 
-	let ulisting = listing_resource.get().unwrap();
-
-	let (listing_data, set_listing_data) = create_signal(ulisting);
+    view! {
+        <Suspense fallback=move || view! { <p>"Loading..."</p> }>
+            {move || listing_resource.get().map(|listing| match listing {
+                Ok(Some(listing)) => {
+                    let title = format!("{} - {}", listing.get_company(), listing.get_position());
+                    let (listing_data, _) = create_signal(listing);
 
-	view! {
-		<Listing listing_data=listing_data />
-	}
+                    view! {
+                        <Title text=title/>
+                        <Listing listing_data=listing_data />
+                    }.into_view()
+                },
+                Ok(None) => view! { <NotFound/> }.into_view(),
+                Err(_) => view! { <p>"Failed to load listing"</p> }.into_view(),
+            })}
+        </Suspense>
+    }
 }
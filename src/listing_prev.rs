@@ -6,6 +6,14 @@ pub fn ListingPrev(
     position: String,
 	description: String,
 	id: i64,
+	// Short labels like location, work mode and pay, see Listing::get_tags
+	#[prop(optional)]
+	tags: Vec<String>,
+	// Average rating from 0 to 1 and how many reviews it comes from
+	#[prop(optional)]
+	rating: f64,
+	#[prop(optional)]
+	reviews: i64,
 ) -> impl IntoView {
     view! {
 		<a href={format!("/listing/{}", id)} style="text-decoration:none">
@@ -13,6 +21,16 @@ pub fn ListingPrev(
 				<div class="left-items">
 					<div class="listing-company">{company_name}</div>
 					<div class="listing-position">{position}</div>
+					<div class="listing-rating">
+						{if reviews == 0 {
+							"No reviews yet".to_string()
+						} else {
+							format!("★ {:.1} ({} {})", rating * 5.0, reviews, if reviews == 1 { "review" } else { "reviews" })
+						}}
+					</div>
+					<div class="listing-tags">
+						{tags.into_iter().map(|tag| view! { <span class="listing-tag">{tag}</span> }).collect_view()}
+					</div>
 				</div>
 				<div class="right-items">
 					<div class="listing-description">{description}</div>
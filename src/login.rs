@@ -7,7 +7,10 @@ use serde::{Deserialize, Serialize};
 
 cfg_if! {
 	if #[cfg(feature = "ssr")] {
-		use crate::db::db;
+		use crate::store::{store, User, Session};
+		use crate::session::get_session;
+		use crate::verify_email::{check_email, check_school_email, send_verification};
+		use crate::role::{Role, SiteAdmins};
 		use tokio;
 
 		use pbkdf2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
@@ -24,54 +27,113 @@ cfg_if! {
 			let session_token = u128::from_le_bytes(u128_pool);
 			let expiry_date = (chrono::Utc::now() + chrono::Duration::hours(1)).timestamp_millis();
 
-			let mut conn = db().await?;
-			let rows = sqlx::query!("INSERT INTO sessions (session_token, user_id, expiry_date) VALUES ($1, $2, $3)",
-				session_token.to_string(), id, expiry_date)
-			.execute(&mut conn).await?;
+			store()?.create_session(Session {
+				session_token: session_token.to_string(),
+				user_id: id,
+				expiry_date,
+			}).await?;
 
 			Ok((session_token.to_string(), expiry_date))
 		}
 
 		pub async fn validate_session(id: i32, session_token: String) -> Result<bool, ServerFnError> {
-			let mut conn = db().await?;
-			let rows = sqlx::query!("SELECT * FROM sessions WHERE user_id = $1 AND session_token = $2",
-				id, session_token)
-				.fetch_all(&mut conn).await?;
+			let session = store()?.get_session(id, &session_token).await?;
 
-			if rows.len() == 0 {
+			let Some(session) = session else {
 				return Ok(false);
-			}
-
-			let expiry_date = rows[0].expiry_date;
+			};
 
-			if expiry_date < chrono::Utc::now().timestamp_millis() {
+			if session.expiry_date < chrono::Utc::now().timestamp_millis() {
 				return Ok(false);
 			}
 
 			Ok(true)
 		}
+
+		// Looks up the user behind the session cookie, or None if there is no valid session
+		pub async fn current_user() -> Result<Option<User>, ServerFnError> {
+			let Some(session) = get_session().await? else {
+				return Ok(None);
+			};
+
+			if !validate_session(session.user_id, session.token).await? {
+				return Ok(None);
+			}
+
+			let Some(mut user) = store()?.get_user(session.user_id).await? else {
+				return Ok(None);
+			};
+
+			// Banned users are treated as logged out
+			if user.banned {
+				return Ok(None);
+			}
+
+			if use_context::<SiteAdmins>().map_or(false, |admins| admins.includes(&user)) {
+				user.role = Role::SiteAdmin;
+			}
+			Ok(Some(user))
+		}
 	}
 }
 
+// The new account's session, whether its school's emails can be verified, and if so whether the verification email could be sent
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Registered {
+	pub session: SessionModel,
+	pub can_verify: bool,
+	pub verification_sent: bool,
+}
+
 #[server]
-pub async fn create_user(username: String, password: String, school: String) -> Result<SessionModel, ServerFnError> {
+pub async fn create_user(username: String, password: String, school_id: i64, email: String) -> Result<Registered, ServerFnError> {
+	if store()?.get_school(school_id).await?.is_none() {
+		return Err(ServerFnError::ServerError("Unknown school".to_string()));
+	}
+
+	// Schools without known email domains, like free-text ones and "Unknown", can't verify their students yet.
+	// Those register unverified with any address; everyone else has to use their school email.
+	let can_verify = !store()?.get_school_domains(school_id).await?.is_empty();
+	let email = if can_verify {
+		check_school_email(&email, school_id).await?
+	} else {
+		check_email(&email)
+	}.map_err(ServerFnError::ServerError)?;
+
+	if store()?.get_user_by_email(&email).await?.is_some() {
+		return Err(ServerFnError::ServerError("That email is already in use".to_string()));
+	}
+
 	let salt = SaltString::generate(&mut OsRng);
 	let hashed_password = Pbkdf2.hash_password(password.as_bytes(), &salt).unwrap().to_string();
 
 	let id = rand::random::<i32>();
 
-	let mut conn = db().await?;
-	let rows = sqlx::query!("INSERT INTO users (id, name, password, school) VALUES ($1, $2, $3, $4)",
-		id, username, hashed_password, school)
-		.execute(&mut conn).await?;
+	store()?.create_user(User {
+		id,
+		name: username,
+		password: hashed_password,
+		school_id,
+		email: Some(email.clone()),
+		verified: false,
+		banned: false,
+		role: Role::User,
+	}).await?;
+
+	// The account works without it, and a new link can be sent from the profile
+	let verification_sent = can_verify && send_verification(id, &email).await.is_ok();
 
 	let (session_token, expiry_date) = create_session(id).await?;
 
 	debug_assert!(validate_session(id, session_token.clone()).await?);
 
-	Ok(SessionModel {
-		token: session_token,
-		user_id: id,
+	Ok(Registered {
+		session: SessionModel {
+			token: session_token,
+			user_id: id,
+		},
+		can_verify,
+		verification_sent,
 	})
 }
 
@@ -79,15 +141,15 @@ pub async fn create_user(username: String, password: String, school: String) ->
 pub async fn login_user(username: String, password: String) -> Result<Result<SessionModel, String>, ServerFnError> {
 	println!("Logging in user...");
 
-	let mut conn = db().await?;
-	let rows = sqlx::query!("SELECT * FROM users WHERE name = $1", username)
-		.fetch_all(&mut conn).await?;
+	let user = store()?.get_user_by_name(&username).await?;
 
-	if rows.len() == 0 {
+	let Some(user) = user else {
 		return Ok(Err("User not found".to_string()));
-	}
+	};
 
-	let user = &rows[0];
+	if user.banned {
+		return Ok(Err("This account has been banned".to_string()));
+	}
 	let hashed_password = PasswordHash::new(&user.password).unwrap();
 
 	match Pbkdf2.verify_password(password.as_bytes(), &hashed_password) {
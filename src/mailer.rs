@@ -0,0 +1,87 @@
+use cfg_if::cfg_if;
+
+/*
+Sends the emails the server needs, like verification links.
+
+Anything implementing Mailer can be plugged in. MAILER picks one of the built in ones:
+"log" prints emails, "file:<dir>" writes each one to a file in dir. Both are for local use,
+and the server refuses to start without one of them rather than quietly dropping emails.
+*/
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use std::path::PathBuf;
+        use std::sync::Arc;
+        use async_trait::async_trait;
+        use leptos::*;
+
+        #[derive(Clone, Debug)]
+        pub struct Email {
+            pub to: String,
+            pub subject: String,
+            pub body: String,
+        }
+
+        #[async_trait]
+        pub trait Mailer: Send + Sync {
+            async fn send(&self, email: Email) -> Result<(), ServerFnError>;
+        }
+
+        pub type DynMailer = Arc<dyn Mailer>;
+
+        // Prints emails to stdout, for local development
+        pub struct LogMailer;
+
+        #[async_trait]
+        impl Mailer for LogMailer {
+            async fn send(&self, email: Email) -> Result<(), ServerFnError> {
+                println!("email to {}: {}\n{}", email.to, email.subject, email.body);
+                Ok(())
+            }
+        }
+
+        // Writes each email to its own file, for local development
+        pub struct FileMailer {
+            dir: PathBuf,
+        }
+
+        impl FileMailer {
+            pub fn new(dir: impl Into<PathBuf>) -> Self {
+                FileMailer { dir: dir.into() }
+            }
+        }
+
+        #[async_trait]
+        impl Mailer for FileMailer {
+            async fn send(&self, email: Email) -> Result<(), ServerFnError> {
+                tokio::fs::create_dir_all(&self.dir).await?;
+
+                let name = format!("{}-{}.eml", chrono::Utc::now().timestamp_millis(), rand::random::<u32>());
+                let contents = format!("To: {}\nSubject: {}\n\n{}\n", email.to, email.subject, email.body);
+                tokio::fs::write(self.dir.join(name), contents).await?;
+                Ok(())
+            }
+        }
+
+        // Picks the mailer from MAILER. Called once at startup from main.rs.
+        pub fn create_mailer() -> Result<DynMailer, String> {
+            let mailer = std::env::var("MAILER").unwrap_or_default();
+            match mailer.strip_prefix("file:") {
+                Some(dir) if !dir.is_empty() => Ok(Arc::new(FileMailer::new(dir))),
+                _ if mailer == "log" => Ok(Arc::new(LogMailer)),
+                _ => Err(format!("MAILER must be \"log\" or \"file:<dir>\", not {:?}", mailer)),
+            }
+        }
+
+        // Gets the mailer provided as context to every server function and route
+        pub fn mailer() -> Result<DynMailer, ServerFnError> {
+            use_context::<DynMailer>()
+                .ok_or_else(|| ServerFnError::ServerError("Mailer missing".to_string()))
+        }
+
+        // Base URL for links in emails, e.g. https://interniverse.example.com
+        pub fn site_url() -> String {
+            std::env::var("SITE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string())
+        }
+    }
+}
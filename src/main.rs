@@ -11,16 +11,38 @@ async fn main() -> std::io::Result<()> {
     use actix_session::{storage::CookieSessionStore, SessionMiddleware};
     use actix_session::config::{BrowserSession, CookieContentSecurity};
     use actix_web::cookie::SameSite;
+    use interniverse::db::auto_migrate;
+    use interniverse::store::create_store;
+    use interniverse::mailer::create_mailer;
+    use interniverse::role::SiteAdmins;
 
     let conf = get_configuration(None).await.unwrap();
     let addr = conf.leptos_options.site_addr;
     // Generate the list of routes in your Leptos App
     let routes = generate_route_list(App);
+    // One store (and connection pool) shared by every worker, instead of a connection per request
+    let store = create_store().await.expect("Failed to connect to the database");
+    let mailer = create_mailer().expect("Failed to set up the mailer");
+    let site_admins = SiteAdmins::from_env();
+
+    // `--migrate` applies pending migrations and exits without starting the server
+    let migrate_only = std::env::args().any(|arg| arg == "--migrate");
+    if migrate_only || auto_migrate() {
+        store.migrate().await.expect("Failed to run database migrations");
+        println!("database migrations up to date");
+    }
+    if migrate_only {
+        return Ok(());
+    }
+
     println!("listening on http://{}", &addr);
 
     HttpServer::new(move || {
         let leptos_options = &conf.leptos_options;
         let site_root = &leptos_options.site_root;
+        let store = store.clone();
+        let mailer = mailer.clone();
+        let site_admins = site_admins.clone();
 
         App::new()
             .wrap(IdentityMiddleware::default())
@@ -28,15 +50,34 @@ async fn main() -> std::io::Result<()> {
                 CookieSessionStore::default(), Key::from(&[0; 64])
             )
                 .build())
-            .route("/api/{tail:.*}", leptos_actix::handle_server_fns())
+            .route("/api/{tail:.*}", leptos_actix::handle_server_fns_with_context({
+                let store = store.clone();
+                let mailer = mailer.clone();
+                let site_admins = site_admins.clone();
+                move || {
+                    provide_context(store.clone());
+                    provide_context(mailer.clone());
+                    provide_context(site_admins.clone());
+                }
+            }))
             // serve JS/WASM/CSS from `pkg`
             .service(Files::new("/pkg", format!("{site_root}/pkg")))
             // serve other assets from the `assets` directory
             .service(Files::new("/assets", site_root))
             // serve the favicon from /favicon.ico
             .service(favicon)
-            .leptos_routes(leptos_options.to_owned(), routes.to_owned(), App)
+            .leptos_routes_with_context(leptos_options.to_owned(), routes.to_owned(), {
+                let store = store.clone();
+                let mailer = mailer.clone();
+                let site_admins = site_admins.clone();
+                move || {
+                    provide_context(store.clone());
+                    provide_context(mailer.clone());
+                    provide_context(site_admins.clone());
+                }
+            }, App)
             .app_data(web::Data::new(leptos_options.to_owned()))
+            .app_data(web::Data::new(store.clone()))
 
     })
     .bind(&addr)?
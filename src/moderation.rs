@@ -0,0 +1,489 @@
+use leptos::*;
+use leptos_meta::Title;
+use cfg_if::cfg_if;
+use serde::{Deserialize, Serialize};
+use crate::comment::format_timestamp;
+
+cfg_if! {
+	if #[cfg(feature = "ssr")] {
+		use crate::store::store;
+		use crate::login::current_user;
+		use crate::role::{require_role, Role};
+		use crate::mailer::{mailer, Email};
+
+		// Most reasons need no explanation, so details are optional but capped
+		const MAX_DETAILS_LENGTH: usize = 1000;
+
+		// Saves a report on a comment or a listing from report_comment or report_listing
+		async fn add_report(reporter_id: i32, comment_id: Option<i64>, listing_id: Option<i64>, reason: ReportReason, details: String) -> Result<Result<(), String>, ServerFnError> {
+			let details = details.trim().to_string();
+			if details.len() > MAX_DETAILS_LENGTH {
+				return Ok(Err(format!("Details can be at most {} characters", MAX_DETAILS_LENGTH)));
+			}
+
+			// The id is assigned by the store. Reporting the same thing twice keeps the first report.
+			store()?.add_report(Report {
+				id: 0,
+				reporter_id,
+				comment_id,
+				listing_id,
+				reason,
+				details,
+				timestamp: chrono::Utc::now().timestamp_millis(),
+				resolved_at: None,
+			}).await?;
+			Ok(Ok(()))
+		}
+	}
+}
+
+// Why something was reported
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::Type))]
+#[cfg_attr(feature = "ssr", sqlx(type_name = "report_reason", rename_all = "lowercase"))]
+pub enum ReportReason {
+    Spam,
+    Harassment,
+    // A made up review or listing
+    Fake,
+    Offensive,
+    Other,
+}
+
+impl ReportReason {
+    pub const ALL: [ReportReason; 5] = [ReportReason::Spam, ReportReason::Harassment, ReportReason::Fake, ReportReason::Offensive, ReportReason::Other];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReportReason::Spam => "Spam",
+            ReportReason::Harassment => "Harassment",
+            ReportReason::Fake => "Fake or misleading",
+            ReportReason::Offensive => "Offensive",
+            ReportReason::Other => "Something else",
+        }
+    }
+}
+
+// What a moderator can do about a report
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::Type))]
+#[cfg_attr(feature = "ssr", sqlx(type_name = "moderation_action", rename_all = "lowercase"))]
+pub enum ModerationAction {
+    // Closes the report without touching the content
+    Dismiss,
+    Hide,
+    Delete,
+    // Emails the author. The report stays open.
+    Warn,
+    // Stops the author from logging in. The report stays open.
+    Ban,
+}
+
+impl ModerationAction {
+    pub const ALL: [ModerationAction; 5] = [ModerationAction::Dismiss, ModerationAction::Hide, ModerationAction::Delete, ModerationAction::Warn, ModerationAction::Ban];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ModerationAction::Dismiss => "Dismiss",
+            ModerationAction::Hide => "Hide",
+            ModerationAction::Delete => "Delete",
+            ModerationAction::Warn => "Warn author",
+            ModerationAction::Ban => "Ban author",
+        }
+    }
+
+    // Whether taking the action closes the report
+    pub fn resolves(&self) -> bool {
+        !matches!(self, ModerationAction::Warn | ModerationAction::Ban)
+    }
+}
+
+// A user flagging a comment or a listing. Exactly one of comment_id and listing_id is set.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct Report {
+    pub id: i64,
+    pub reporter_id: i32,
+    pub comment_id: Option<i64>,
+    pub listing_id: Option<i64>,
+    pub reason: ReportReason,
+    pub details: String,
+    // Milliseconds since the epoch, like comment timestamps
+    pub timestamp: i64,
+    // When a moderator dealt with it, None while it is open
+    pub resolved_at: Option<i64>,
+}
+
+// An open report along with what was reported, as shown in the moderation queue
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QueueEntry {
+    pub report: Report,
+    pub reporter: String,
+    // "Comment" or "Listing"
+    pub kind: String,
+    pub content: String,
+    // The real name of whoever posted it, even for anonymous comments. None if unknown.
+    pub author: Option<String>,
+    // Where the content can be seen
+    pub listing_id: i64,
+    pub hidden: bool,
+}
+
+// One action a moderator took
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct ModerationLogEntry {
+    pub id: i64,
+    pub moderator_id: i32,
+    pub action: ModerationAction,
+    pub report_id: Option<i64>,
+    pub comment_id: Option<i64>,
+    pub listing_id: Option<i64>,
+    // The author of the reported content
+    pub user_id: Option<i32>,
+    pub note: String,
+    pub timestamp: i64,
+}
+
+#[server(ReportComment, "/report-comment")]
+pub async fn report_comment(comment_id: i64, reason: ReportReason, details: String) -> Result<Result<(), String>, ServerFnError> {
+    let Some(user) = current_user().await? else {
+        return Ok(Err("You must be logged in to report content".to_string()));
+    };
+
+    if store()?.get_comment(comment_id).await?.is_none() {
+        return Ok(Err("This comment no longer exists".to_string()));
+    }
+
+    add_report(user.id, Some(comment_id), None, reason, details).await
+}
+
+#[server(ReportListing, "/report-listing")]
+pub async fn report_listing(listing_id: i64, reason: ReportReason, details: String) -> Result<Result<(), String>, ServerFnError> {
+    let Some(user) = current_user().await? else {
+        return Ok(Err("You must be logged in to report content".to_string()));
+    };
+
+    if store()?.get_listing(listing_id).await?.is_none() {
+        return Ok(Err("This listing no longer exists".to_string()));
+    }
+
+    add_report(user.id, None, Some(listing_id), reason, details).await
+}
+
+// Open reports, oldest first, with the content they are about
+#[server(GetModerationQueue, "/moderation-queue")]
+pub async fn get_moderation_queue() -> Result<Result<Vec<QueueEntry>, String>, ServerFnError> {
+    if require_role(Role::Moderator).await?.is_none() {
+        return Ok(Err("Only moderators can see reports".to_string()));
+    }
+
+    let mut queue = Vec::new();
+    for report in store()?.get_open_reports().await? {
+        let reporter = store()?.get_user(report.reporter_id).await?.map(|user| user.name).unwrap_or_default();
+
+        let entry = match (report.comment_id, report.listing_id) {
+            (Some(comment_id), _) => store()?.get_comment(comment_id).await?.map(|comment| QueueEntry {
+                kind: "Comment".to_string(),
+                content: comment.content,
+                author: Some(comment.author),
+                listing_id: comment.listing_id,
+                hidden: comment.hidden,
+                reporter,
+                report,
+            }),
+            (None, Some(listing_id)) => match store()?.get_listing(listing_id).await? {
+                Some(listing) => {
+                    let author = match listing.user_id {
+                        Some(user_id) => store()?.get_user(user_id).await?.map(|user| user.name),
+                        None => None,
+                    };
+
+                    Some(QueueEntry {
+                        kind: "Listing".to_string(),
+                        content: format!("{} at {}: {}", listing.position, listing.company, listing.description),
+                        author,
+                        listing_id: listing.id,
+                        hidden: listing.hidden,
+                        reporter,
+                        report,
+                    })
+                },
+                None => None,
+            },
+            (None, None) => None,
+        };
+
+        queue.extend(entry);
+    }
+    Ok(Ok(queue))
+}
+
+// Acts on a report and records the action in the moderation log
+#[server(Moderate, "/moderate")]
+pub async fn moderate(report_id: i64, action: ModerationAction, note: String) -> Result<Result<(), String>, ServerFnError> {
+    let Some(moderator) = require_role(Role::Moderator).await? else {
+        return Ok(Err("Only moderators can act on reports".to_string()));
+    };
+
+    let Some(report) = store()?.get_report(report_id).await? else {
+        return Ok(Err("This report no longer exists".to_string()));
+    };
+
+    let author_id = match (report.comment_id, report.listing_id) {
+        (Some(comment_id), _) => store()?.get_comment(comment_id).await?.and_then(|comment| comment.user_id),
+        (None, Some(listing_id)) => store()?.get_listing(listing_id).await?.and_then(|listing| listing.user_id),
+        (None, None) => None,
+    };
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let note = note.trim().to_string();
+
+    // Resolved first, since deleting the content deletes its reports
+    if action.resolves() {
+        store()?.resolve_reports(&report, now).await?;
+    }
+
+    match action {
+        ModerationAction::Dismiss => {},
+        ModerationAction::Hide => match (report.comment_id, report.listing_id) {
+            (Some(comment_id), _) => store()?.set_comment_hidden(comment_id, true).await?,
+            (None, Some(listing_id)) => store()?.set_listing_hidden(listing_id, true).await?,
+            (None, None) => {},
+        },
+        ModerationAction::Delete => match (report.comment_id, report.listing_id) {
+            // delete_comment only blanks a comment with replies, so it's hidden like Hide too, and stays down with its thread
+            (Some(comment_id), _) => {
+                store()?.delete_comment(comment_id, moderator.id).await?;
+                store()?.set_comment_hidden(comment_id, true).await?;
+            },
+            (None, Some(listing_id)) => store()?.delete_listing(listing_id).await?,
+            (None, None) => {},
+        },
+        ModerationAction::Warn | ModerationAction::Ban => {
+            let Some(author) = (match author_id {
+                Some(author_id) => store()?.get_user(author_id).await?,
+                None => None,
+            }) else {
+                return Ok(Err("The author of this content is unknown".to_string()));
+            };
+
+            if author.id == moderator.id {
+                return Ok(Err("You can't warn or ban yourself".to_string()));
+            }
+
+            if author.role.includes(Role::Moderator) && !moderator.role.includes(Role::SiteAdmin) {
+                return Ok(Err("Only site admins can warn or ban other moderators and admins".to_string()));
+            }
+
+            if action == ModerationAction::Ban {
+                store()?.set_banned(author.id, true).await?;
+            } else if let Some(email) = author.email {
+                let reason = if note.is_empty() { String::new() } else { format!("\n\nModerator's note: {}", note) };
+
+                mailer()?.send(Email {
+                    to: email,
+                    subject: "A warning about your Interniverse post".to_string(),
+                    body: format!("Something you posted was reported as {} and reviewed by a moderator.{}\n\nPosts that break the rules again may be removed and your account banned.",
+                        report.reason.label().to_lowercase(), reason),
+                }).await?;
+            }
+        },
+    }
+
+    // The id is assigned by the store
+    store()?.add_moderation_log_entry(ModerationLogEntry {
+        id: 0,
+        moderator_id: moderator.id,
+        action,
+        report_id: Some(report.id),
+        comment_id: report.comment_id,
+        listing_id: report.listing_id,
+        user_id: author_id,
+        note,
+        timestamp: now,
+    }).await?;
+    Ok(Ok(()))
+}
+
+// The latest moderation actions, newest first
+#[server(GetModerationLog, "/moderation-log")]
+pub async fn get_moderation_log() -> Result<Result<Vec<ModerationLogEntry>, String>, ServerFnError> {
+    if require_role(Role::Moderator).await?.is_none() {
+        return Ok(Err("Only moderators can see the moderation log".to_string()));
+    }
+
+    Ok(Ok(store()?.get_moderation_log(50).await?))
+}
+
+// What a ReportButton reports
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportTarget {
+    Comment(i64),
+    Listing(i64),
+}
+
+/// A "Report" button that opens a form for the reason, for logged in users
+#[component]
+pub fn ReportButton(target: ReportTarget, #[prop(into)] logged_in: Signal<bool>) -> impl IntoView {
+    let (open, set_open) = create_signal(false);
+    let (reason, set_reason) = create_signal(None::<ReportReason>);
+    let (details, set_details) = create_signal(String::new());
+    let (status, set_status) = create_signal(String::new());
+
+    let on_submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        let Some(reason) = reason.get_untracked() else {
+            set_status("Choose a reason".to_string());
+            return;
+        };
+        let details = details.get_untracked();
+
+        spawn_local(async move {
+            let result = match target {
+                ReportTarget::Comment(id) => report_comment(id, reason, details).await,
+                ReportTarget::Listing(id) => report_listing(id, reason, details).await,
+            };
+
+            match result {
+                Ok(Ok(())) => {
+                    set_open(false);
+                    set_details(String::new());
+                    set_reason(None);
+                    set_status("Thanks, a moderator will take a look".to_string());
+                },
+                Ok(Err(e)) => set_status(e),
+                Err(e) => set_status("Failed to report: ".to_string() + e.to_string().as_str()),
+            }
+        });
+    };
+
+    view! {
+        <Show when=move || logged_in.get() fallback=|| ()>
+            <span class="report">
+                <button class="comment-action" on:click=move |_| set_open.update(|open| *open = !*open)>
+                    {move || if open.get() { "Cancel report" } else { "Report" }}
+                </button>
+                <span class="report-status">{status}</span>
+                <Show when=move || open.get() fallback=|| ()>
+                    <form class="report-form" on:submit=on_submit>
+                        <select on:change=move |ev| {
+                            let value = event_target_value(&ev);
+                            set_reason(ReportReason::ALL.into_iter().find(|reason| format!("{:?}", reason) == value));
+                        }>
+                            <option value="" selected=move || reason.get().is_none()>"Why are you reporting this?"</option>
+                            {ReportReason::ALL.into_iter().map(|option| view! {
+                                <option value=format!("{:?}", option) selected=move || reason.get() == Some(option)>{option.label()}</option>
+                            }).collect_view()}
+                        </select>
+                        <input type="text"
+                            placeholder="Details (optional)"
+                            prop:value=details
+                            on:input=move |ev| set_details(event_target_value(&ev))
+                        />
+                        <input class="comment-submit" type="submit" value="Report"/>
+                    </form>
+                </Show>
+            </span>
+        </Show>
+    }
+}
+
+#[component]
+fn QueueEntryView(entry: QueueEntry, on_done: Callback<()>) -> impl IntoView {
+    let report_id = entry.report.id;
+    let (note, set_note) = create_signal(String::new());
+    let (status, set_status) = create_signal(String::new());
+
+    let on_action = move |action: ModerationAction| {
+        let note = note.get_untracked();
+
+        spawn_local(async move {
+            match moderate(report_id, action, note).await {
+                Ok(Ok(())) => {
+                    set_note(String::new());
+                    set_status(format!("{} done", action.label()));
+                    on_done.call(());
+                },
+                Ok(Err(e)) => set_status(e),
+                Err(e) => set_status("Failed to moderate: ".to_string() + e.to_string().as_str()),
+            }
+        });
+    };
+
+    view! {
+        <div class="moderation-entry">
+            <div class="moderation-entry-header">
+                <b>{entry.kind}</b>
+                " by "{entry.author.unwrap_or_else(|| "an unknown author".to_string())}
+                {entry.hidden.then_some(" (hidden)")}
+                " · "<a href=format!("/listing/{}", entry.listing_id)>"View listing"</a>
+            </div>
+            <p class="moderation-entry-content">{entry.content}</p>
+            <p class="moderation-entry-reason">
+                {entry.report.reason.label()}" · reported by "{entry.reporter}" on "{format_timestamp(entry.report.timestamp)}
+            </p>
+            {(!entry.report.details.is_empty()).then(|| view! { <p class="moderation-entry-details">{entry.report.details.clone()}</p> })}
+            <input type="text"
+                class="moderation-note"
+                placeholder="Note for the log (sent to the author with a warning)"
+                prop:value=note
+                on:input=move |ev| set_note(event_target_value(&ev))
+            />
+            <div class="moderation-actions">
+                {ModerationAction::ALL.into_iter().map(|action| view! {
+                    <button class="moderation-action" on:click=move |_| on_action(action)>{action.label()}</button>
+                }).collect_view()}
+            </div>
+            <p class="comment-status">{status}</p>
+        </div>
+    }
+}
+
+/// Renders the open reports for moderators to act on, and the latest actions taken
+#[component]
+pub fn ModerationPage() -> impl IntoView {
+    let queue = create_resource(|| (), |_| async move { get_moderation_queue().await });
+    let log = create_resource(|| (), |_| async move { get_moderation_log().await });
+
+    let on_done = Callback::new(move |_| {
+        queue.refetch();
+        log.refetch();
+    });
+
+    view! {
+        <Title text="Moderation"/>
+        <div class="moderation">
+            <h1>"Moderation"</h1>
+            <Suspense fallback=move || view! { <p>"Loading reports..."</p> }>
+                {move || queue.get().map(|queue| match queue {
+                    Ok(Ok(entries)) if entries.is_empty() => view! { <p>"No open reports."</p> }.into_view(),
+                    Ok(Ok(entries)) => entries.into_iter()
+                        .map(|entry| view! { <QueueEntryView entry=entry on_done=on_done/> })
+                        .collect_view(),
+                    Ok(Err(e)) => view! { <p>{e}</p> }.into_view(),
+                    Err(_) => view! { <p>"Failed to load reports"</p> }.into_view(),
+                })}
+            </Suspense>
+            <h2>"Log"</h2>
+            <Suspense fallback=|| ()>
+                {move || log.get().map(|log| match log {
+                    Ok(Ok(entries)) => view! {
+                        <ul class="moderation-log">
+                            {entries.into_iter().map(|entry| view! {
+                                <li>
+                                    {format_timestamp(entry.timestamp)}" · "{entry.action.label()}
+                                    {entry.comment_id.map(|id| format!(" comment #{}", id))}
+                                    {entry.listing_id.map(|id| format!(" listing #{}", id))}
+                                    {(!entry.note.is_empty()).then(|| format!(" · {}", entry.note))}
+                                </li>
+                            }).collect_view()}
+                        </ul>
+                    }.into_view(),
+                    _ => ().into_view(),
+                })}
+            </Suspense>
+            <a href="/">"Back to listings"</a>
+        </div>
+    }
+}
@@ -0,0 +1,254 @@
+use leptos::*;
+use leptos::leptos_dom::logging::console_log;
+use leptos_meta::Title;
+use leptos_router::use_navigate;
+use chrono::NaiveDate;
+use crate::listing::{add_listing, ListingErrors, NewListing, Season, WorkMode};
+use crate::school::{School, SchoolPicker};
+
+/// Renders the new post page of your application.
+/// This is where users can create new posts.
+#[component]
+pub fn NewPost() -> impl IntoView {
+    let (company, set_company) = create_signal("".to_string());
+    let (position, set_position) = create_signal("".to_string());
+    let (description, set_description) = create_signal("".to_string());
+    let (url, set_url) = create_signal("".to_string());
+    let school = create_rw_signal(None::<School>);
+    let (location, set_location) = create_signal("".to_string());
+    let (work_mode, set_work_mode) = create_signal(None::<WorkMode>);
+    let (paid, set_paid) = create_signal(None::<bool>);
+    let (hourly_rate, set_hourly_rate) = create_signal("".to_string());
+    let (season, set_season) = create_signal(None::<Season>);
+    let (year, set_year) = create_signal("".to_string());
+    let (deadline, set_deadline) = create_signal("".to_string());
+
+    let (errors, set_errors) = create_signal(ListingErrors::default());
+    let (status, set_status) = create_signal("".to_string());
+
+    // Stored so each submission's task can use it
+    let navigate = store_value(use_navigate());
+
+    let on_submit = move |_| {
+        let hourly_rate = hourly_rate.get();
+        let hourly_rate = match hourly_rate.trim() {
+            "" => None,
+            rate => match rate.parse::<f64>() {
+                Ok(rate) => Some(rate),
+                Err(_) => {
+                    set_errors.update(|errors| errors.pay = Some("Hourly rate must be a number".to_string()));
+                    return;
+                }
+            },
+        };
+
+        // The deadline is the end of the chosen day
+        let deadline = NaiveDate::parse_from_str(&deadline.get(), "%Y-%m-%d").ok()
+            .and_then(|date| date.and_hms_opt(23, 59, 59))
+            .map(|date| date.timestamp_millis());
+
+        let listing = NewListing {
+            company: company.get(),
+            position: position.get(),
+            description: description.get(),
+            url: url.get(),
+            school_id: school.get().map(|school| school.id),
+            location: location.get(),
+            work_mode: work_mode.get(),
+            paid: paid.get(),
+            hourly_rate: if paid.get() == Some(true) { hourly_rate } else { None },
+            season: season.get(),
+            year: year.get().trim().parse().ok(),
+            deadline,
+        };
+
+        spawn_local(async move {
+            set_status("Posting...".to_string());
+
+            match add_listing(listing).await {
+                Ok(Ok(id)) => {
+                    set_status("".to_string());
+                    set_errors(ListingErrors::default());
+
+                    navigate.with_value(|navigate| navigate(&format!("/listing/{}", id), Default::default()));
+                },
+                Ok(Err(e)) => {
+                    set_status("".to_string());
+                    set_errors(e);
+                },
+                Err(e) => {
+                    console_log(&("Error: ".to_string() + e.to_string().as_str()));
+                    set_status("Failed to post listing: ".to_string() + e.to_string().as_str());
+                }
+            }
+        })
+    };
+
+    // Error message shown under a field, if it has one
+    let field_error = move |get: fn(&ListingErrors) -> &Option<String>| {
+        move || errors.with(|errors| get(errors).clone()).map(|e| view! { <p class="field-error">{e}</p> })
+    };
+
+    view! {
+        <Title text="New Post"/>
+        <div class="new-post">
+            <h1>"New Post"</h1>
+            <p>{status}</p>
+            <label for="new-post-company-input"><b>Company</b></label>
+            <input
+                class="login-input"
+                name="new-post-company-input"
+                type="text"
+                on:input=move |ev| {
+                    set_company(event_target_value(&ev));
+                }
+
+                prop:value=company
+            />
+            {field_error(|errors| &errors.company)}
+            <label for="new-post-position-input"><b>Position</b></label>
+            <input
+                class="login-input"
+                name="new-post-position-input"
+                type="text"
+                on:input=move |ev| {
+                    set_position(event_target_value(&ev));
+                }
+
+                prop:value=position
+            />
+            {field_error(|errors| &errors.position)}
+            <label for="new-post-description-input"><b>Description</b></label>
+            <textarea
+                class="login-input"
+                name="new-post-description-input"
+                rows="8"
+                on:input=move |ev| {
+                    set_description(event_target_value(&ev));
+                }
+
+                prop:value=description
+            />
+            {field_error(|errors| &errors.description)}
+            <label for="new-post-url-input"><b>URL</b></label>
+            <input
+                class="login-input"
+                name="new-post-url-input"
+                type="url"
+                placeholder="https://"
+                on:input=move |ev| {
+                    set_url(event_target_value(&ev));
+                }
+
+                prop:value=url
+            />
+            {field_error(|errors| &errors.url)}
+            <label for="new-post-school-input"><b>School</b></label>
+            <SchoolPicker selected=school name="new-post-school-input"/>
+            {field_error(|errors| &errors.school)}
+            <label for="new-post-location-input"><b>Location</b></label>
+            <input
+                class="login-input"
+                name="new-post-location-input"
+                type="text"
+                placeholder="City, State"
+                on:input=move |ev| {
+                    set_location(event_target_value(&ev));
+                }
+
+                prop:value=location
+            />
+            {field_error(|errors| &errors.location)}
+            <label for="new-post-work-mode-input"><b>Work Mode</b></label>
+            <select
+                class="login-input"
+                name="new-post-work-mode-input"
+                on:change=move |ev| {
+                    let value = event_target_value(&ev);
+                    set_work_mode(WorkMode::ALL.into_iter().find(|mode| format!("{:?}", mode) == value));
+                }
+            >
+                <option value="" selected=move || work_mode.get().is_none()>"Choose..."</option>
+                {WorkMode::ALL.into_iter().map(|mode| view! {
+                    <option value=format!("{:?}", mode) selected=move || work_mode.get() == Some(mode)>{mode.label()}</option>
+                }).collect_view()}
+            </select>
+            {field_error(|errors| &errors.work_mode)}
+            <label for="new-post-paid-input"><b>Pay</b></label>
+            <select
+                class="login-input"
+                name="new-post-paid-input"
+                on:change=move |ev| {
+                    set_paid(match event_target_value(&ev).as_str() {
+                        "paid" => Some(true),
+                        "unpaid" => Some(false),
+                        _ => None,
+                    });
+                }
+            >
+                <option value="" selected=move || paid.get().is_none()>"Choose..."</option>
+                <option value="paid" selected=move || paid.get() == Some(true)>"Paid"</option>
+                <option value="unpaid" selected=move || paid.get() == Some(false)>"Unpaid"</option>
+            </select>
+            <Show when=move || paid.get() == Some(true) fallback=|| ()>
+                <label for="new-post-hourly-rate-input"><b>Hourly Rate (optional)</b></label>
+                <input
+                    class="login-input"
+                    name="new-post-hourly-rate-input"
+                    type="number"
+                    min="0"
+                    step="0.01"
+                    placeholder="$/hr"
+                    on:input=move |ev| {
+                        set_hourly_rate(event_target_value(&ev));
+                    }
+
+                    prop:value=hourly_rate
+                />
+            </Show>
+            {field_error(|errors| &errors.pay)}
+            <label for="new-post-season-input"><b>Season</b></label>
+            <div class="new-post-row">
+                <select
+                    class="login-input"
+                    name="new-post-season-input"
+                    on:change=move |ev| {
+                        let value = event_target_value(&ev);
+                        set_season(Season::ALL.into_iter().find(|season| format!("{:?}", season) == value));
+                    }
+                >
+                    <option value="" selected=move || season.get().is_none()>"Choose..."</option>
+                    {Season::ALL.into_iter().map(|option| view! {
+                        <option value=format!("{:?}", option) selected=move || season.get() == Some(option)>{option.label()}</option>
+                    }).collect_view()}
+                </select>
+                <input
+                    class="login-input"
+                    name="new-post-year-input"
+                    type="number"
+                    placeholder="Year"
+                    on:input=move |ev| {
+                        set_year(event_target_value(&ev));
+                    }
+
+                    prop:value=year
+                />
+            </div>
+            {field_error(|errors| &errors.season)}
+            <label for="new-post-deadline-input"><b>Application Deadline (optional)</b></label>
+            <input
+                class="login-input"
+                name="new-post-deadline-input"
+                type="date"
+                on:input=move |ev| {
+                    set_deadline(event_target_value(&ev));
+                }
+
+                prop:value=deadline
+            />
+            {field_error(|errors| &errors.author)}
+            <button class="login-button" on:click=on_submit>Post</button>
+            <a href="/">"Cancel"</a>
+        </div>
+    }
+}
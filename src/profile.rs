@@ -1,50 +1,67 @@
 use leptos::*;
 use leptos::leptos_dom::logging::console_log;
 use crate::popup::Popup;
-use crate::session::{end_session, get_session};
-
-#[cfg(feature = "ssr")]
-use crate::db::db;
+use crate::session::end_session;
+use crate::school::School;
+use crate::verify_email::send_verification_email;
+use crate::role::Role;
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 pub struct ProfileData {
     name: String,
-    school: String,
+    school: School,
+    email: Option<String>,
+    // Whether the user has confirmed their school email
+    verified: bool,
+    role: Role,
 }
 
-#[server(GetProfile)]
-pub async fn get_profile() -> Result<Option<ProfileData>, ServerFnError> {
-    use crate::login::validate_session;
-
-    let session = get_session().await?;
+impl ProfileData {
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
 
-    if session.is_none() {
-        return Ok(None)
+    pub fn get_school(&self) -> &School {
+        &self.school
     }
 
-    let session = session.unwrap();
+    pub fn get_email(&self) -> &Option<String> {
+        &self.email
+    }
 
-    let validated = validate_session(session.user_id, session.token).await;
+    pub fn is_verified(&self) -> bool {
+        self.verified
+    }
 
-    if validated.is_err() || !validated.unwrap() {
-        return Ok(None)
+    pub fn get_role(&self) -> Role {
+        self.role
     }
 
-    let mut conn = db().await?;
+    // Whether the user may do what the given role may, for showing controls only they can use
+    pub fn has_role(&self, role: Role) -> bool {
+        self.role.includes(role)
+    }
+}
 
-    let rows = sqlx::query!("SELECT * FROM users WHERE id = $1", session.user_id)
-        .fetch_all(&mut conn).await?;
+#[server(GetProfile)]
+pub async fn get_profile() -> Result<Option<ProfileData>, ServerFnError> {
+    use crate::login::current_user;
+    use crate::store::store;
 
-    if rows.len() == 0 {
+    let Some(user) = current_user().await? else {
         return Ok(None);
-    }
+    };
 
-    let user = &rows[0];
+    let school = store()?.get_school(user.school_id).await?
+        .ok_or_else(|| ServerFnError::ServerError("School missing".to_string()))?;
 
     Ok(Some(
         ProfileData {
-            name: user.name.clone(),
-            school: user.school.clone(),
+            name: user.name,
+            school,
+            email: user.email,
+            verified: user.verified,
+            role: user.role,
         }
     ))
 }
@@ -71,11 +88,40 @@ pub fn Profile(open: RwSignal<bool>, reload_profile: RwSignal<bool>) -> impl Int
         }
     });
 
+    let (email, set_email) = create_signal("".to_string());
+    let (verify_status, set_verify_status) = create_signal("".to_string());
+
+    // Starts from the email the user registered with, if any
+    create_effect(move |_| {
+        if let Some(Some(profile)) = profile.get() {
+            set_email(profile.email.unwrap_or_default());
+        }
+    });
+
+    let send_verification = move |_| {
+        spawn_local(async move {
+            set_verify_status("Sending...".to_string());
+
+            match send_verification_email(email.get_untracked()).await {
+                Ok(Ok(())) => {
+                    set_verify_status("Check your inbox for a verification link".to_string());
+                    profile.refetch();
+                },
+                Ok(Err(e)) => set_verify_status(e),
+                Err(e) => {
+                    console_log(&("Error: ".to_string() + e.to_string().as_str()));
+                    set_verify_status("Failed to send: ".to_string() + e.to_string().as_str());
+                }
+            }
+        });
+    };
+
     let logout = move |_| {
         spawn_local(async move {
             end_session().await.ok();
 
             open.set(false);
+            reload_profile.set(true);
         });
     };
 
@@ -91,7 +137,27 @@ pub fn Profile(open: RwSignal<bool>, reload_profile: RwSignal<bool>) -> impl Int
                     fallback=move || view! { <p>"Failed to get profile"</p> }
                 >
                 <p><b>Name: </b>{profile.get().unwrap().unwrap().name}</p>
-                <p><b>School: </b>{profile.get().unwrap().unwrap().school}</p>
+                <p><b>School: </b>{profile.get().unwrap().unwrap().school.name}</p>
+                <Show
+                    when=move || profile.get().flatten().map_or(false, |profile| profile.verified)
+                    fallback=move || view! {
+                        <p><b>"Not verified. "</b>"Verify your school email to post listings."</p>
+                        <input
+                            class="login-input"
+                            name="profile-email-input"
+                            type="email"
+                            on:input=move |ev| {
+                                set_email(event_target_value(&ev));
+                            }
+
+                            prop:value=email
+                        />
+                        <button class="login-button" on:click=send_verification>"Send verification link"</button>
+                        <p>{verify_status}</p>
+                    }
+                >
+                    <p><b>"Verified: "</b>{profile.get().flatten().and_then(|profile| profile.email)}</p>
+                </Show>
                 <button class="login-button" on:click=logout>Logout</button>
                 </Show>
                 </Suspense>
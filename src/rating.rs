@@ -0,0 +1,91 @@
+use leptos::*;
+use cfg_if::cfg_if;
+use serde::{Deserialize, Serialize};
+
+cfg_if! {
+	if #[cfg(feature = "ssr")] {
+		use crate::store::store;
+	}
+}
+
+// Bayesian scores start every listing at 3 stars, weighted like this many reviews
+pub const PRIOR_RATING: f64 = 0.6;
+pub const PRIOR_REVIEWS: f64 = 5.0;
+
+// Lowest ratings that count as 2, 3, 4 and 5 stars in the histogram. The SQL stores bucket comments the same way.
+const STAR_THRESHOLDS: [f64; 4] = [0.3, 0.5, 0.7, 0.9];
+
+// Stars from 1 to 5 that a rating from 0 to 1 counts as in the histogram
+pub fn stars(rating: f64) -> usize {
+    STAR_THRESHOLDS.iter().filter(|threshold| rating >= **threshold).count() + 1
+}
+
+// The reviews of a listing or company, summed up on the server
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RatingSummary {
+    // Average rating from 0 to 1, 0 when there are no reviews
+    pub average: f64,
+    pub count: i64,
+    // Number of reviews with 1 to 5 stars
+    pub histogram: [i64; 5],
+    // The average pulled towards PRIOR_RATING, so a few reviews can't outrank many
+    pub score: f64,
+}
+
+impl RatingSummary {
+    pub fn new(average: f64, histogram: [i64; 5]) -> Self {
+        let count = histogram.iter().sum::<i64>();
+        let score = (PRIOR_RATING * PRIOR_REVIEWS + average * count as f64) / (PRIOR_REVIEWS + count as f64);
+
+        RatingSummary { average, count, histogram, score }
+    }
+
+    pub fn from_ratings(ratings: impl IntoIterator<Item = f64>) -> Self {
+        let mut sum = 0.0;
+        let mut histogram = [0; 5];
+        for rating in ratings {
+            sum += rating;
+            histogram[stars(rating) - 1] += 1;
+        }
+
+        let count = histogram.iter().sum::<i64>();
+        Self::new(if count == 0 { 0.0 } else { sum / count as f64 }, histogram)
+    }
+
+    // Like "4.2 stars from 12 reviews"
+    pub fn label(&self) -> String {
+        if self.count == 0 {
+            "No reviews yet".to_string()
+        } else {
+            format!("{:.1} stars from {} {}", self.average * 5.0, self.count, if self.count == 1 { "review" } else { "reviews" })
+        }
+    }
+}
+
+#[server(GetRatingSummary, "/rating-summary")]
+pub async fn get_rating_summary(listing_id: i64) -> Result<Option<RatingSummary>, ServerFnError> {
+    store()?.get_rating_summary(listing_id).await
+}
+
+// One bar per star count, 5 stars first, each as wide as its share of the reviews
+pub fn rating_histogram(summary: &RatingSummary) -> View {
+    if summary.count == 0 {
+        return ().into_view();
+    }
+
+    let count = summary.count;
+
+    view! {
+        <div class="rating-histogram">
+            {summary.histogram.into_iter().enumerate().rev().map(|(index, reviews)| view! {
+                <div class="rating-histogram-row">
+                    <span class="rating-histogram-label">{index + 1}" ★"</span>
+                    <div class="rating-histogram-bar">
+                        <div class="rating-histogram-fill" style=format!("width: {}%", reviews * 100 / count)/>
+                    </div>
+                    <span class="rating-histogram-count">{reviews}</span>
+                </div>
+            }).collect_view()}
+        </div>
+    }.into_view()
+}
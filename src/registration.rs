@@ -3,32 +3,43 @@ use crate::popup::Popup;
 use crate::login::*;
 use leptos::{*, ev::SubmitEvent, leptos_dom::logging::console_log};
 use crate::session::set_session;
+use crate::school::{School, SchoolPicker};
 
 #[component]
 pub fn Registration(open: RwSignal<bool>, reload_profile: RwSignal<bool>, login_open: RwSignal<bool>) -> impl IntoView {
     let (username, set_username) = create_signal("".to_string());
     let (password, set_password) = create_signal("".to_string());
-    let (school, set_school) = create_signal("".to_string());
+    let school = create_rw_signal(None::<School>);
+    let (email, set_email) = create_signal("".to_string());
     let (github, set_github) = create_signal("".to_string());
     let (linkedin, set_linkedin) = create_signal("".to_string());
 
     let (status, set_status) = create_signal("".to_string());
 
     let on_submit = move |_| {
+        let Some(school_id) = school.get().map(|school| school.id) else {
+            set_status("Choose your school from the list".to_string());
+            return;
+        };
+
         spawn_local(async move {
             console_log("Registering...");
             set_status("Registering...".to_string());
 
-            let session = create_user(username.get(), password.get(), school.get()).await;
+            let registered = create_user(username.get(), password.get(), school_id, email.get()).await;
 
-            match session {
-                Ok(session) => {
-                    set_session(session).await.expect("Failed to set session");
+            match registered {
+                Ok(registered) => {
+                    set_session(registered.session).await.expect("Failed to set session");
                     console_log("Registered");
                     set_status("".to_string());
 
                     reload_profile.set(true);
                     open.set(false);
+
+                    if registered.can_verify && !registered.verification_sent {
+                        let _ = window().alert_with_message("Your account was created, but the verification email couldn't be sent. You can send it again from your profile.");
+                    }
                 },
                 Err(e) => {
                     console_log(&("Error: ".to_string() + e.to_string().as_str()));
@@ -71,15 +82,18 @@ pub fn Registration(open: RwSignal<bool>, reload_profile: RwSignal<bool>, login_
                     prop:value=password
                 />
                 <label for="login-school-input"><b>School</b></label>
+                <SchoolPicker selected=school name="login-school-input"/>
+                <label for="login-email-input"><b>School Email</b></label>
                 <input
                     class="login-input"
-                    name="login-school-input"
-                    type="text"
+                    name="login-email-input"
+                    type="email"
+                    placeholder="We'll send you a link to verify it"
                     on:input=move |ev| {
-                        set_school(event_target_value(&ev));
+                        set_email(event_target_value(&ev));
                     }
 
-                    prop:value=school
+                    prop:value=email
                 />
                 <label for="login-github-input"><b>GitHub</b></label>
                 <input
@@ -0,0 +1,130 @@
+use cfg_if::cfg_if;
+use serde::{Deserialize, Serialize};
+
+cfg_if! {
+	if #[cfg(feature = "ssr")] {
+		use leptos::ServerFnError;
+		use crate::store::User;
+		use crate::login::current_user;
+
+		// The logged in user, if their role includes this one. Server functions call it before doing anything only the role may do:
+		// let Some(user) = require_role(Role::Moderator).await? else { ... };
+		pub async fn require_role(role: Role) -> Result<Option<User>, ServerFnError> {
+			Ok(current_user().await?.filter(|user| user.role.includes(role)))
+		}
+
+		// Users whose verified email is listed in SITE_ADMINS, separated by commas, are site admins whatever their stored role, so the
+		// first admin can be set up. Names can't be used, since anyone can register one that isn't taken yet.
+		// Read once at startup and provided as context, like the store.
+		#[derive(Clone, Debug, Default)]
+		pub struct SiteAdmins(Vec<String>);
+
+		impl SiteAdmins {
+			pub fn from_env() -> Self {
+				Self::parse(&std::env::var("SITE_ADMINS").unwrap_or_default())
+			}
+
+			fn parse(emails: &str) -> Self {
+				SiteAdmins(emails.split(',').map(|email| email.trim().to_lowercase()).filter(|email| !email.is_empty()).collect())
+			}
+
+			pub fn includes(&self, user: &User) -> bool {
+				user.verified && user.email.as_ref().map_or(false, |email| self.0.contains(email))
+			}
+		}
+	}
+}
+
+// What a user is allowed to do. Each role can do everything the ones before it can.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::Type))]
+#[cfg_attr(feature = "ssr", sqlx(type_name = "user_role", rename_all = "snake_case"))]
+pub enum Role {
+    #[default]
+    User,
+    // Acts on reports and sees who posted anonymous comments
+    Moderator,
+    // Appoints the moderators of their own school
+    SchoolAdmin,
+    // Appoints anyone, including school admins
+    SiteAdmin,
+}
+
+impl Role {
+    pub const ALL: [Role; 4] = [Role::User, Role::Moderator, Role::SchoolAdmin, Role::SiteAdmin];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Role::User => "User",
+            Role::Moderator => "Moderator",
+            Role::SchoolAdmin => "School admin",
+            Role::SiteAdmin => "Site admin",
+        }
+    }
+
+    // Whether someone with this role may do what the given role may
+    pub fn includes(&self, role: Role) -> bool {
+        *self >= role
+    }
+
+    // Whether someone with this role may give the given role to a user, or take it away
+    pub fn can_assign(&self, role: Role) -> bool {
+        match self {
+            Role::SiteAdmin => true,
+            Role::SchoolAdmin => role <= Role::Moderator,
+            Role::Moderator | Role::User => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roles_include_the_ones_before_them() {
+        for (i, role) in Role::ALL.into_iter().enumerate() {
+            for (j, other) in Role::ALL.into_iter().enumerate() {
+                assert_eq!(role.includes(other), i >= j, "{:?} including {:?}", role, other);
+            }
+        }
+    }
+
+    #[test]
+    fn only_admins_assign_roles() {
+        // Rows are the assigning role and columns the role assigned, both in the order of Role::ALL
+        let allowed = [
+            [false, false, false, false],
+            [false, false, false, false],
+            [true, true, false, false],
+            [true, true, true, true],
+        ];
+
+        for (i, admin) in Role::ALL.into_iter().enumerate() {
+            for (j, role) in Role::ALL.into_iter().enumerate() {
+                assert_eq!(admin.can_assign(role), allowed[i][j], "{:?} assigning {:?}", admin, role);
+            }
+        }
+    }
+
+    #[cfg(feature = "ssr")]
+    #[test]
+    fn site_admins_need_a_verified_listed_email() {
+        let admins = SiteAdmins::parse(" Admin@UCLA.edu, ,root@example.com");
+        let user = |email: Option<&str>, verified| User {
+            id: 1,
+            name: "admin@ucla.edu".to_string(),
+            password: "hash".to_string(),
+            school_id: 1,
+            email: email.map(String::from),
+            verified,
+            banned: false,
+            role: Role::User,
+        };
+
+        assert!(admins.includes(&user(Some("admin@ucla.edu"), true)));
+        assert!(!admins.includes(&user(Some("admin@ucla.edu"), false)), "unverified");
+        assert!(!admins.includes(&user(Some("other@ucla.edu"), true)), "named like an admin email");
+        assert!(!admins.includes(&user(None, true)));
+    }
+}
@@ -0,0 +1,79 @@
+use leptos::*;
+use cfg_if::cfg_if;
+use serde::{Deserialize, Serialize};
+
+cfg_if! {
+	if #[cfg(feature = "ssr")] {
+		use crate::store::store;
+	}
+}
+
+// Most suggestions shown by the school picker
+pub const SCHOOL_SUGGESTIONS: i64 = 10;
+
+// A school under its canonical name. Aliases and email domains stay on the server.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct School {
+    pub id: i64,
+    pub name: String,
+}
+
+// Schools whose name or one of its aliases contains the query, ignoring case
+#[server(SearchSchools, "/search-schools")]
+pub async fn search_schools(query: String) -> Result<Vec<School>, ServerFnError> {
+    let query = query.trim();
+
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    store()?.search_schools(query, SCHOOL_SUGGESTIONS).await
+}
+
+// Text input that suggests schools as you type. Picking a suggestion sets selected.
+#[component]
+pub fn SchoolPicker(selected: RwSignal<Option<School>>, #[prop(into)] name: String) -> impl IntoView {
+    let (query, set_query) = create_signal(String::new());
+
+    let suggestions = create_resource(
+        move || query.get(),
+        |query| async move { search_schools(query).await.unwrap_or_default() },
+    );
+
+    view! {
+        <div class="school-picker">
+            <input
+                class="login-input"
+                name=name
+                type="text"
+                placeholder="Start typing your school..."
+                on:input=move |ev| {
+                    // Typing again means the previous pick no longer matches the text
+                    selected.set(None);
+                    set_query(event_target_value(&ev));
+                }
+
+                prop:value=move || selected.get().map(|school| school.name).unwrap_or_else(|| query.get())
+            />
+            <Show when=move || selected.with(|selected| selected.is_none()) && !query.with(|query| query.trim().is_empty()) fallback=|| ()>
+                <Suspense fallback=|| ()>
+                    <ul class="school-suggestions">
+                        {move || suggestions.get().map(|schools| {
+                            if schools.is_empty() {
+                                return view! { <li class="school-suggestion-empty">"No matching schools"</li> }.into_view();
+                            }
+
+                            schools.into_iter().map(|school| {
+                                let label = school.name.clone();
+                                view! {
+                                    <li class="school-suggestion" on:click=move |_| selected.set(Some(school.clone()))>{label}</li>
+                                }
+                            }).collect_view()
+                        })}
+                    </ul>
+                </Suspense>
+            </Show>
+        </div>
+    }
+}
@@ -46,6 +46,33 @@ pub async fn end_session() -> Result<(), ServerFnError> {
     Ok(())
 }
 
+// Whether the home page only shows listings from the user's school. Kept in the session so it survives reloads.
+#[server(GetSchoolOnly)]
+pub async fn get_school_only() -> Result<bool, ServerFnError> {
+    use leptos_actix::extract;
+    use actix_session::Session;
+
+    let school_only = extract(
+        |session: Session| async move {
+            session.get::<bool>("school_only")
+        }
+    ).await??;
+
+    Ok(school_only.unwrap_or(false))
+}
+
+#[server(SetSchoolOnly)]
+pub async fn set_school_only(school_only: bool) -> Result<(), ServerFnError> {
+    use leptos_actix::extract;
+    use actix_session::Session;
+
+    Ok(extract(
+        move |session: Session| async move {
+            session.insert("school_only", school_only)
+        }
+    ).await??)
+}
+
 #[component]
 pub fn SessionPage() -> impl IntoView {
     let (current, set_current) = create_signal("".to_string());
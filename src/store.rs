@@ -0,0 +1,393 @@
+use cfg_if::cfg_if;
+
+/*
+Storage layer used by the server functions.
+
+Each table has a repository trait. The Postgres implementation is what runs in production,
+the in-memory one lets the server functions run without a database (DATABASE_URL=memory).
+*/
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        pub mod postgres;
+        pub mod memory;
+        #[cfg(feature = "sqlite")]
+        pub mod sqlite;
+
+        use std::sync::Arc;
+        use async_trait::async_trait;
+        use leptos::*;
+        use sqlx::QueryBuilder;
+        use crate::listing::{Listing, ListingCursor, ListingFilters, ListingSort, NewListing, Season, WorkMode};
+        use crate::rating::{RatingSummary, PRIOR_RATING, PRIOR_REVIEWS};
+        use crate::interview::{InterviewReport, InterviewStats};
+        use crate::compensation::CompensationReport;
+        use crate::comment::{Comment, CommentRevision, CommentSort, DimensionRatings};
+        use crate::school::School;
+        use crate::company::{Company, CompanyDetails};
+        use crate::moderation::{ModerationLogEntry, Report};
+        use crate::role::Role;
+
+        // A row of the users table
+        #[derive(Clone, Debug, sqlx::FromRow)]
+        pub struct User {
+            pub id: i32,
+            pub name: String,
+            pub password: String,
+            pub school_id: i64,
+            // Lowercase. Users who registered before emails were required don't have one.
+            pub email: Option<String>,
+            pub verified: bool,
+            // Banned by a moderator. Banned users can't log in and their sessions stop working.
+            pub banned: bool,
+            pub role: Role,
+        }
+
+        // A row of the sessions table
+        #[derive(Clone, Debug, sqlx::FromRow)]
+        pub struct Session {
+            pub session_token: String,
+            pub user_id: i32,
+            pub expiry_date: i64,
+        }
+
+        // A row of the email_verifications table, a one-time link sent to confirm an email address
+        #[derive(Clone, Debug, sqlx::FromRow)]
+        pub struct EmailVerification {
+            pub token: String,
+            pub user_id: i32,
+            pub email: String,
+            pub expiry_date: i64,
+        }
+
+        // The rating columns kept up to date on listings and companies whenever a comment is added
+        #[derive(Clone, Debug, sqlx::FromRow)]
+        pub struct RatingAggregate {
+            pub rating_average: f64,
+            pub rating_count: i64,
+            pub stars_1: i64,
+            pub stars_2: i64,
+            pub stars_3: i64,
+            pub stars_4: i64,
+            pub stars_5: i64,
+        }
+
+        impl RatingAggregate {
+            pub fn into_summary(self) -> RatingSummary {
+                RatingSummary::new(self.rating_average, [self.stars_1, self.stars_2, self.stars_3, self.stars_4, self.stars_5])
+            }
+        }
+
+        // A row of the companies table with its ratings
+        #[derive(Clone, Debug, sqlx::FromRow)]
+        pub struct CompanyRow {
+            #[sqlx(flatten)]
+            pub company: Company,
+            #[sqlx(flatten)]
+            pub ratings: RatingAggregate,
+            #[sqlx(flatten)]
+            pub dimensions: DimensionRatings,
+        }
+
+        impl CompanyRow {
+            pub fn into_details(self) -> CompanyDetails {
+                CompanyDetails {
+                    company: self.company,
+                    ratings: self.ratings.into_summary(),
+                    dimensions: self.dimensions,
+                }
+            }
+        }
+
+        // SQL shared by the Postgres and SQLite stores. Only what differs between the two, like full-text search, case
+        // insensitive matching and row locking, is left to each backend.
+
+        // Columns selected into a Listing, with the listings table aliased as l
+        pub const LISTING_COLUMNS: &str = "l.company, l.company_id, l.position, l.description, l.url, l.id, l.school, l.school_id, l.location, l.work_mode, l.paid, l.hourly_rate, l.season, l.year, l.deadline,
+            l.rating_average AS rating, l.rating_count AS reviews, l.rating_score AS score, l.hidden, l.user_id";
+
+        // Ratings stored on listings and companies
+        pub const RATING_COLUMNS: &str = "rating_average, rating_count, stars_1, stars_2, stars_3, stars_4, stars_5";
+
+        // RATING_COLUMNS computed over the comments table aliased as c, bucketed into stars like rating::stars.
+        // Replies have no rating, so the queries using this leave them out.
+        pub const RATING_AGGREGATES: &str = "COALESCE(AVG(c.rating), 0.0), COUNT(c.id),
+            COUNT(c.id) FILTER (WHERE c.rating < 0.3), COUNT(c.id) FILTER (WHERE c.rating >= 0.3 AND c.rating < 0.5),
+            COUNT(c.id) FILTER (WHERE c.rating >= 0.5 AND c.rating < 0.7), COUNT(c.id) FILTER (WHERE c.rating >= 0.7 AND c.rating < 0.9),
+            COUNT(c.id) FILTER (WHERE c.rating >= 0.9)";
+
+        // Columns selected into a Comment, from COMMENT_TABLES.
+        // The viewer_vote column is added by each query, since it depends on who is asking.
+        pub const COMMENT_COLUMNS: &str = "c.author, c.content, c.timestamp, c.rating, c.listing_id, c.id, COALESCE(u.verified, FALSE) AS author_verified,
+            c.mentorship, c.compensation, c.work_life_balance, c.learning, c.return_offer, c.parent_id, c.edited_at, c.user_id, c.anonymous, p.number AS pseudonym, s.name AS author_school, c.hidden, c.deleted, c.deleted_by,
+            (SELECT COALESCE(SUM(v.value), 0) FROM comment_votes v WHERE v.comment_id = c.id) AS helpful";
+
+        // The comments table aliased as c, with its author's user, school and pseudonym on the listing
+        pub const COMMENT_TABLES: &str = "comments c LEFT JOIN users u ON u.id = c.user_id LEFT JOIN schools s ON s.id = u.school_id
+            LEFT JOIN listing_pseudonyms p ON p.listing_id = c.listing_id AND p.user_id = c.user_id";
+
+        // Averages of the rating dimensions over the comments table aliased as c
+        pub const DIMENSION_AVERAGES: &str = "AVG(c.mentorship) AS mentorship, AVG(c.compensation) AS compensation, AVG(c.work_life_balance) AS work_life_balance,
+            AVG(c.learning) AS learning, AVG(c.return_offer) AS return_offer";
+
+        // Statements recomputing the ratings stored on the listings matching the condition, and on their companies, from their
+        // comments. Hidden and deleted comments are left out, and hidden listings don't count towards their company.
+        pub fn refresh_ratings_sql(listings: &str) -> [String; 3] {
+            [
+                format!("UPDATE listings SET ({RATING_COLUMNS}) = (SELECT {RATING_AGGREGATES} FROM comments c WHERE c.listing_id = listings.id AND c.parent_id IS NULL AND NOT c.hidden AND NOT c.deleted)
+                    WHERE {listings}"),
+                format!("UPDATE listings SET rating_score = ({PRIOR_RATING} * {PRIOR_REVIEWS} + rating_average * rating_count) / ({PRIOR_REVIEWS} + rating_count)
+                    WHERE {listings}"),
+                format!("UPDATE companies SET ({RATING_COLUMNS}) = (
+                        SELECT {RATING_AGGREGATES} FROM comments c JOIN listings l ON l.id = c.listing_id
+                        WHERE l.company_id = companies.id AND c.parent_id IS NULL AND NOT c.hidden AND NOT c.deleted AND NOT l.hidden
+                    )
+                    WHERE id IN (SELECT company_id FROM listings WHERE {listings})"),
+            ]
+        }
+
+        // Escapes the LIKE wildcards in user input, for use with ESCAPE '\'
+        pub fn escape_like(value: &str) -> String {
+            value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+        }
+
+        // A value the shared query builders can bind on any backend
+        pub trait SqlValue<'a, DB: sqlx::Database>: 'a + Send + sqlx::Encode<'a, DB> + sqlx::Type<DB> {}
+        impl<'a, DB: sqlx::Database, T: 'a + Send + sqlx::Encode<'a, DB> + sqlx::Type<DB>> SqlValue<'a, DB> for T {}
+
+        // Appends the filters as AND conditions on the listings table, aliased as l.
+        // like is the backend's case insensitive LIKE operator.
+        pub fn push_filters<'a, DB>(query: &mut QueryBuilder<'a, DB>, filters: &ListingFilters, like: &str)
+        where
+            DB: sqlx::Database,
+            i64: SqlValue<'a, DB>,
+            i32: SqlValue<'a, DB>,
+            bool: SqlValue<'a, DB>,
+            String: SqlValue<'a, DB>,
+            WorkMode: SqlValue<'a, DB>,
+            Season: SqlValue<'a, DB>,
+        {
+            if let Some(school_id) = filters.school_id {
+                query.push(" AND l.school_id = ").push_bind(school_id);
+            }
+            if let Some(company_id) = filters.company_id {
+                query.push(" AND l.company_id = ").push_bind(company_id);
+            }
+            if let Some(location) = &filters.location {
+                query.push(format!(" AND l.location {like} ")).push_bind(format!("%{}%", escape_like(location))).push(" ESCAPE '\\'");
+            }
+            if let Some(work_mode) = filters.work_mode {
+                query.push(" AND l.work_mode = ").push_bind(work_mode);
+            }
+            if let Some(paid) = filters.paid {
+                query.push(" AND l.paid = ").push_bind(paid);
+            }
+            if let Some(season) = filters.season {
+                query.push(" AND l.season = ").push_bind(season);
+            }
+            if let Some(year) = filters.year {
+                query.push(" AND l.year = ").push_bind(year);
+            }
+        }
+
+        // Selects a page of ListingStore::list_listings, with like as in push_filters
+        pub fn list_listings_query<'a, DB>(filters: &ListingFilters, sort: ListingSort, after: Option<&ListingCursor>, limit: i64, like: &str) -> QueryBuilder<'a, DB>
+        where
+            DB: sqlx::Database,
+            <DB as sqlx::database::HasArguments<'a>>::Arguments: Default,
+            i64: SqlValue<'a, DB>,
+            i32: SqlValue<'a, DB>,
+            f64: SqlValue<'a, DB>,
+            bool: SqlValue<'a, DB>,
+            String: SqlValue<'a, DB>,
+            WorkMode: SqlValue<'a, DB>,
+            Season: SqlValue<'a, DB>,
+        {
+            let (key, order, comparison) = match sort {
+                ListingSort::Newest => ("l.id", "DESC", "<"),
+                ListingSort::HighestRated => ("l.rating_score", "DESC", "<"),
+                ListingSort::MostReviewed => ("l.rating_count", "DESC", "<"),
+                ListingSort::Company => ("l.company", "ASC", ">"),
+            };
+
+            let mut query = QueryBuilder::new(format!("SELECT {LISTING_COLUMNS} FROM listings l WHERE NOT l.hidden"));
+            push_filters(&mut query, filters, like);
+
+            // Compares (sort key, id) against the cursor so ties on the sort key don't repeat or skip listings
+            if let Some(after) = after {
+                query.push(format!(" AND ({key}, l.id) {comparison} ("));
+                match sort {
+                    ListingSort::Newest => query.push_bind(after.id),
+                    ListingSort::HighestRated => query.push_bind(after.score),
+                    ListingSort::MostReviewed => query.push_bind(after.reviews),
+                    ListingSort::Company => query.push_bind(after.company.clone()),
+                };
+                query.push(", ").push_bind(after.id).push(")");
+            }
+
+            query.push(format!(" ORDER BY {key} {order}, l.id {order} LIMIT ")).push_bind(limit);
+            query
+        }
+
+        // Pairs each listing with its cursor, for returning from ListingStore::list_listings
+        pub fn with_cursors(listings: Vec<Listing>) -> Vec<(Listing, ListingCursor)> {
+            listings.into_iter().map(|listing| {
+                let cursor = listing.get_cursor();
+                (listing, cursor)
+            }).collect()
+        }
+
+        #[async_trait]
+        pub trait ListingStore: Send + Sync {
+            // Listings in the given order, starting after the cursor, along with each listing's own cursor
+            async fn list_listings(&self, filters: &ListingFilters, sort: ListingSort, after: Option<&ListingCursor>, limit: i64) -> Result<Vec<(Listing, ListingCursor)>, ServerFnError>;
+            async fn get_listing(&self, id: i64) -> Result<Option<Listing>, ServerFnError>;
+            // Ranked full-text search. An empty query matches everything, newest first.
+            async fn search_listings(&self, query: &str, filters: &ListingFilters, limit: i64, offset: i64) -> Result<Vec<Listing>, ServerFnError>;
+            // Adds the listing under the company, using the company's canonical name
+            async fn add_listing(&self, listing: NewListing, company_id: i64, user_id: i32) -> Result<i64, ServerFnError>;
+            // Also updates the ratings stored on the listing's company, which leave hidden listings out
+            async fn set_listing_hidden(&self, id: i64, hidden: bool) -> Result<(), ServerFnError>;
+            // Deletes the listing with everything posted on it, and updates its company's ratings
+            async fn delete_listing(&self, id: i64) -> Result<(), ServerFnError>;
+            async fn get_rating_summary(&self, listing_id: i64) -> Result<Option<RatingSummary>, ServerFnError>;
+        }
+
+        #[async_trait]
+        pub trait CompanyStore: Send + Sync {
+            async fn get_company(&self, id: i64) -> Result<Option<CompanyDetails>, ServerFnError>;
+            // The company with this name or alias, ignoring case, or a new one under this name
+            async fn find_or_create_company(&self, name: &str) -> Result<Company, ServerFnError>;
+        }
+
+        #[async_trait]
+        pub trait SchoolStore: Send + Sync {
+            // Schools whose name or an alias contains the query, ignoring case, by name
+            async fn search_schools(&self, query: &str, limit: i64) -> Result<Vec<School>, ServerFnError>;
+            async fn get_school(&self, id: i64) -> Result<Option<School>, ServerFnError>;
+            // Lowercase email domains, like ucla.edu, that belong to the school
+            async fn get_school_domains(&self, school_id: i64) -> Result<Vec<String>, ServerFnError>;
+        }
+
+        #[async_trait]
+        pub trait CommentStore: Send + Sync {
+            // Reviews and replies alike, replies pointing at their parent. viewer is the user whose votes are filled in.
+            async fn get_comments(&self, listing_id: i64, sort: CommentSort, viewer: Option<i32>) -> Result<Vec<Comment>, ServerFnError>;
+            async fn get_comment(&self, id: i64) -> Result<Option<Comment>, ServerFnError>;
+            // The user's review of the listing. Replies aren't reviews.
+            async fn get_review(&self, listing_id: i64, user_id: i32) -> Result<Option<Comment>, ServerFnError>;
+            // Also updates the ratings stored on the listing and its company. Returns false, adding nothing, for a review by a user
+            // who already has one on the listing.
+            async fn add_comment(&self, comment: Comment) -> Result<bool, ServerFnError>;
+            // Saves the comment's new content, ratings and edited_at, keeping the version it replaces as a revision, and updates the
+            // stored ratings like add_comment. Deleted comments are left as they are.
+            async fn edit_comment(&self, comment: Comment) -> Result<(), ServerFnError>;
+            // Saves new content on a comment like edit_comment, and brings it back if its author deleted it themselves.
+            // Comments that aren't deleted, or that someone else deleted, are left as they are.
+            async fn restore_comment(&self, comment: Comment) -> Result<(), ServerFnError>;
+            // Deletes the comment along with its votes and revisions, and updates the stored ratings.
+            // A comment with replies is kept with its content blanked and marked deleted by deleted_by instead, so the replies stay threaded.
+            async fn delete_comment(&self, id: i64, deleted_by: i32) -> Result<(), ServerFnError>;
+            // Newest first
+            async fn get_comment_revisions(&self, comment_id: i64) -> Result<Vec<CommentRevision>, ServerFnError>;
+            // Hidden comments don't count towards ratings, so this updates the stored ratings too
+            async fn set_comment_hidden(&self, id: i64, hidden: bool) -> Result<(), ServerFnError>;
+            // Replaces the user's vote on the comment, if they had one
+            async fn set_vote(&self, comment_id: i64, user_id: i32, value: i32) -> Result<(), ServerFnError>;
+            async fn remove_vote(&self, comment_id: i64, user_id: i32) -> Result<(), ServerFnError>;
+            // Average of each rating dimension over the listing's comments
+            async fn get_dimension_averages(&self, listing_id: i64) -> Result<DimensionRatings, ServerFnError>;
+        }
+
+        #[async_trait]
+        pub trait InterviewStore: Send + Sync {
+            // Newest first
+            async fn get_interview_reports(&self, listing_id: i64) -> Result<Vec<InterviewReport>, ServerFnError>;
+            async fn add_interview_report(&self, report: InterviewReport) -> Result<(), ServerFnError>;
+            // Stats over the reports on all of the company's listings
+            async fn get_interview_stats(&self, company_id: i64) -> Result<InterviewStats, ServerFnError>;
+        }
+
+        #[async_trait]
+        pub trait CompensationStore: Send + Sync {
+            async fn get_compensation_reports(&self, listing_id: i64) -> Result<Vec<CompensationReport>, ServerFnError>;
+            // Reports on all of the company's listings
+            async fn get_company_compensation_reports(&self, company_id: i64) -> Result<Vec<CompensationReport>, ServerFnError>;
+            async fn add_compensation_report(&self, report: CompensationReport) -> Result<(), ServerFnError>;
+        }
+
+        #[async_trait]
+        pub trait ModerationStore: Send + Sync {
+            // Does nothing if the reporter already reported the same content
+            async fn add_report(&self, report: Report) -> Result<(), ServerFnError>;
+            async fn get_report(&self, id: i64) -> Result<Option<Report>, ServerFnError>;
+            // Oldest first
+            async fn get_open_reports(&self) -> Result<Vec<Report>, ServerFnError>;
+            // Resolves every open report on the same content as this one
+            async fn resolve_reports(&self, report: &Report, resolved_at: i64) -> Result<(), ServerFnError>;
+            async fn add_moderation_log_entry(&self, entry: ModerationLogEntry) -> Result<(), ServerFnError>;
+            // Newest first
+            async fn get_moderation_log(&self, limit: i64) -> Result<Vec<ModerationLogEntry>, ServerFnError>;
+        }
+
+        #[async_trait]
+        pub trait UserStore: Send + Sync {
+            async fn create_user(&self, user: User) -> Result<(), ServerFnError>;
+            async fn get_user(&self, id: i32) -> Result<Option<User>, ServerFnError>;
+            async fn get_user_by_name(&self, name: &str) -> Result<Option<User>, ServerFnError>;
+            async fn get_user_by_email(&self, email: &str) -> Result<Option<User>, ServerFnError>;
+            // Changes the user's email, which then needs verifying again
+            async fn set_email(&self, user_id: i32, email: &str) -> Result<(), ServerFnError>;
+            async fn set_verified(&self, user_id: i32) -> Result<(), ServerFnError>;
+            async fn set_banned(&self, user_id: i32, banned: bool) -> Result<(), ServerFnError>;
+            async fn set_role(&self, user_id: i32, role: Role) -> Result<(), ServerFnError>;
+            // Users with a role above User, from one school or from all of them, by name
+            async fn get_staff(&self, school_id: Option<i64>) -> Result<Vec<User>, ServerFnError>;
+            async fn create_verification(&self, verification: EmailVerification) -> Result<(), ServerFnError>;
+            // Removes and returns the verification, so each link only works once
+            async fn take_verification(&self, token: &str) -> Result<Option<EmailVerification>, ServerFnError>;
+        }
+
+        #[async_trait]
+        pub trait SessionStore: Send + Sync {
+            async fn create_session(&self, session: Session) -> Result<(), ServerFnError>;
+            async fn get_session(&self, user_id: i32, session_token: &str) -> Result<Option<Session>, ServerFnError>;
+        }
+
+        // Everything the server functions need from storage
+        #[async_trait]
+        pub trait Store: ListingStore + CompanyStore + SchoolStore + CommentStore + InterviewStore + CompensationStore + ModerationStore + UserStore + SessionStore {
+            // Brings the schema up to date, then recomputes the ratings stored on every listing and company
+            async fn migrate(&self) -> Result<(), ServerFnError>;
+        }
+
+        pub type DynStore = Arc<dyn Store>;
+
+        // Picks the backend from DATABASE_URL. Called once at startup from main.rs.
+        pub async fn create_store() -> Result<DynStore, sqlx::Error> {
+            use crate::db::{create_pool, database_url};
+
+            let url = database_url();
+
+            if url.starts_with("memory") {
+                return Ok(Arc::new(memory::MemoryStore::default()));
+            }
+
+            if url.starts_with("sqlite:") {
+                #[cfg(feature = "sqlite")]
+                return Ok(Arc::new(sqlite::SqliteStore::new(crate::db::create_sqlite_pool().await?)));
+
+                #[cfg(not(feature = "sqlite"))]
+                return Err(sqlx::Error::Configuration("SQLite support requires the sqlite feature".into()));
+            }
+
+            Ok(Arc::new(postgres::PgStore::new(create_pool().await?)))
+        }
+
+        // Gets the store provided as context to every server function and route
+        pub fn store() -> Result<DynStore, ServerFnError> {
+            use_context::<DynStore>()
+                .ok_or_else(|| ServerFnError::ServerError("Store missing".to_string()))
+        }
+    }
+}
@@ -0,0 +1,1115 @@
+use std::sync::{Mutex, MutexGuard};
+use async_trait::async_trait;
+use leptos::*;
+use crate::comment::{Comment, CommentRevision, CommentSort, DimensionRatings};
+use crate::school::School;
+use crate::rating::RatingSummary;
+use crate::compensation::CompensationReport;
+use crate::interview::{InterviewOutcome, InterviewReport, InterviewStats};
+use crate::company::{Company, CompanyDetails};
+use std::cmp::Ordering;
+use crate::listing::{Listing, ListingCursor, ListingFilters, ListingSort, NewListing};
+use super::*;
+
+// A row of the schools table along with its aliases and email domains, all lowercase
+struct SchoolRecord {
+    school: School,
+    aliases: Vec<String>,
+    domains: Vec<String>,
+}
+
+// The schools the SQL migrations start with: name, aliases, email domains
+const SEED_SCHOOLS: [(&str, &[&str], &[&str]); 6] = [
+    ("University of California, Los Angeles", &["ucla"], &["ucla.edu"]),
+    ("University of California, Berkeley", &["uc berkeley", "berkeley", "cal"], &["berkeley.edu"]),
+    ("University of California, San Diego", &["ucsd", "uc san diego"], &["ucsd.edu"]),
+    ("Stanford University", &["stanford"], &["stanford.edu"]),
+    ("University of Southern California", &["usc"], &["usc.edu"]),
+    ("Massachusetts Institute of Technology", &["mit"], &["mit.edu"]),
+];
+
+// A row of the companies table along with its aliases, which are lowercase
+struct CompanyRecord {
+    company: Company,
+    aliases: Vec<String>,
+}
+
+// A row of the comment_votes table
+struct CommentVote {
+    comment_id: i64,
+    user_id: i32,
+    value: i32,
+}
+
+// A row of the listing_pseudonyms table
+struct ListingPseudonym {
+    listing_id: i64,
+    user_id: i32,
+    number: i64,
+}
+
+#[derive(Default)]
+struct Tables {
+    listings: Vec<Listing>,
+    companies: Vec<CompanyRecord>,
+    schools: Vec<SchoolRecord>,
+    comments: Vec<Comment>,
+    votes: Vec<CommentVote>,
+    comment_revisions: Vec<CommentRevision>,
+    pseudonyms: Vec<ListingPseudonym>,
+    interview_reports: Vec<InterviewReport>,
+    compensation_reports: Vec<CompensationReport>,
+    users: Vec<User>,
+    sessions: Vec<Session>,
+    verifications: Vec<EmailVerification>,
+    reports: Vec<Report>,
+    moderation_log: Vec<ModerationLogEntry>,
+}
+
+impl Tables {
+    // The comment with the columns the SQL stores look up when loading it filled in
+    fn load_comment(&self, comment: &Comment, viewer: Option<i32>) -> Comment {
+        let votes = || self.votes.iter().filter(|vote| vote.comment_id == comment.id);
+        let author = self.users.iter().find(|user| Some(user.id) == comment.user_id);
+
+        Comment {
+            author_verified: author.map_or(false, |author| author.verified),
+            pseudonym: self.pseudonyms.iter()
+                .find(|pseudonym| pseudonym.listing_id == comment.listing_id && Some(pseudonym.user_id) == comment.user_id)
+                .map(|pseudonym| pseudonym.number),
+            author_school: author
+                .and_then(|author| self.schools.iter().find(|record| record.school.id == author.school_id))
+                .map(|record| record.school.name.clone()),
+            helpful: votes().map(|vote| vote.value as i64).sum(),
+            viewer_vote: votes().find(|vote| Some(vote.user_id) == viewer).map_or(0, |vote| vote.value),
+            ..comment.clone()
+        }
+    }
+
+    // Comments on the listings that aren't hidden, like the SQL stores count
+    fn visible_comments<'a>(&'a self, listing_ids: &'a [i64]) -> impl Iterator<Item = &'a Comment> {
+        self.comments.iter().filter(|comment| listing_ids.contains(&comment.listing_id) && !comment.hidden)
+    }
+
+    // The visible comments that count towards ratings, which deleted ones kept for their replies don't
+    fn rated_comments<'a>(&'a self, listing_ids: &'a [i64]) -> impl Iterator<Item = &'a Comment> {
+        self.visible_comments(listing_ids).filter(|comment| !comment.deleted)
+    }
+
+    fn ratings(&self, listing_ids: &[i64]) -> RatingSummary {
+        RatingSummary::from_ratings(self.rated_comments(listing_ids)
+            .filter(|comment| comment.parent_id.is_none())
+            .map(|comment| comment.rating))
+    }
+
+    // Recomputes the rating stored on the listing, like the SQL stores do when a comment is added
+    fn refresh_ratings(&mut self, listing_id: i64) {
+        let ratings = self.ratings(&[listing_id]);
+
+        if let Some(listing) = self.listings.iter_mut().find(|listing| listing.id == listing_id) {
+            listing.rating = ratings.average;
+            listing.reviews = ratings.count;
+            listing.score = ratings.score;
+        }
+    }
+}
+
+// Storage that lives only as long as the process. Used for tests and for running without Postgres.
+#[derive(Default)]
+pub struct MemoryStore {
+    tables: Mutex<Tables>,
+}
+
+impl MemoryStore {
+    fn tables(&self) -> MutexGuard<'_, Tables> {
+        // A panic while holding the lock can't leave the tables half-written, so poisoning is ignored
+        self.tables.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // Saves the comment's new content, ratings and edited_at, keeping the version it replaces as a revision.
+    // Like the SQL stores, edits only apply to comments that aren't deleted, and restoring only to ones their own author deleted.
+    fn save_edit(&self, comment: Comment, restore: bool) {
+        let mut tables = self.tables();
+
+        let Some(index) = tables.comments.iter().position(|existing| {
+            existing.id == comment.id && if restore { existing.deleted && existing.deleted_by == existing.user_id } else { !existing.deleted }
+        }) else {
+            return;
+        };
+
+        let previous = &tables.comments[index];
+        let revision = CommentRevision {
+            id: tables.comment_revisions.len() as i64 + 1,
+            comment_id: previous.id,
+            content: previous.content.clone(),
+            rating: previous.rating,
+            dimensions: previous.dimensions,
+            timestamp: previous.edited_at.unwrap_or(previous.timestamp),
+        };
+        tables.comment_revisions.push(revision);
+
+        let existing = &mut tables.comments[index];
+        existing.content = comment.content;
+        existing.rating = comment.rating;
+        existing.dimensions = comment.dimensions;
+        existing.edited_at = comment.edited_at;
+        if restore {
+            existing.deleted = false;
+            existing.deleted_by = None;
+        }
+
+        tables.refresh_ratings(comment.listing_id);
+    }
+}
+
+#[async_trait]
+impl Store for MemoryStore {
+    async fn migrate(&self) -> Result<(), ServerFnError> {
+        // The tables start out empty, so this seeds the schools the migrations would
+        let mut tables = self.tables();
+        if tables.schools.is_empty() {
+            tables.schools = SEED_SCHOOLS.iter().enumerate()
+                .map(|(index, (name, aliases, domains))| SchoolRecord {
+                    school: School { id: index as i64 + 1, name: name.to_string() },
+                    aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+                    domains: domains.iter().map(|domain| domain.to_string()).collect(),
+                })
+                .collect();
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ListingStore for MemoryStore {
+    async fn list_listings(&self, filters: &ListingFilters, sort: ListingSort, after: Option<&ListingCursor>, limit: i64) -> Result<Vec<(Listing, ListingCursor)>, ServerFnError> {
+        let tables = self.tables();
+
+        let mut rows: Vec<(Listing, ListingCursor)> = tables.listings.iter()
+            .filter(|listing| !listing.hidden && filters.matches(listing))
+            .map(|listing| (listing.clone(), listing.get_cursor()))
+            .collect();
+
+        // Same orders as the SQL stores: the sort key, then id to break ties
+        let order = |a: &ListingCursor, b: &ListingCursor| -> Ordering {
+            match sort {
+                ListingSort::Newest => b.id.cmp(&a.id),
+                ListingSort::HighestRated => b.score.total_cmp(&a.score).then(b.id.cmp(&a.id)),
+                ListingSort::MostReviewed => b.reviews.cmp(&a.reviews).then(b.id.cmp(&a.id)),
+                ListingSort::Company => a.company.cmp(&b.company).then(a.id.cmp(&b.id)),
+            }
+        };
+        rows.sort_by(|(_, a), (_, b)| order(a, b));
+
+        Ok(rows.into_iter()
+            .filter(|(_, cursor)| after.map_or(true, |after| order(after, cursor) == Ordering::Less))
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn get_listing(&self, id: i64) -> Result<Option<Listing>, ServerFnError> {
+        Ok(self.tables().listings.iter().find(|listing| listing.id == id).cloned())
+    }
+
+    async fn search_listings(&self, query: &str, filters: &ListingFilters, limit: i64, offset: i64) -> Result<Vec<Listing>, ServerFnError> {
+        let words: Vec<String> = query.split_whitespace().map(|word| word.to_lowercase()).collect();
+
+        // Scores each listing like the Postgres weights: company and position count most, then school, then description
+        let score = |listing: &Listing| -> Option<u32> {
+            let fields = [(&listing.company, 4), (&listing.position, 4), (&listing.school, 2), (&listing.description, 1)];
+            let mut total = 0;
+            for word in &words {
+                let word_score: u32 = fields.iter()
+                    .filter(|(field, _)| field.to_lowercase().contains(word.as_str()))
+                    .map(|(_, weight)| weight)
+                    .sum();
+                if word_score == 0 {
+                    return None;
+                }
+                total += word_score;
+            }
+            Some(total)
+        };
+
+        let mut matches: Vec<(u32, Listing)> = self.tables().listings.iter()
+            .filter(|listing| !listing.hidden && filters.matches(listing))
+            .filter_map(|listing| score(listing).map(|score| (score, listing.clone())))
+            .collect();
+        matches.sort_by(|(a_score, a), (b_score, b)| b_score.cmp(a_score).then(b.id.cmp(&a.id)));
+
+        Ok(matches.into_iter().skip(offset as usize).take(limit as usize).map(|(_, listing)| listing).collect())
+    }
+
+    async fn add_listing(&self, listing: NewListing, company_id: i64, user_id: i32) -> Result<i64, ServerFnError> {
+        let mut tables = self.tables();
+        let school = listing.school_id
+            .and_then(|school_id| tables.schools.iter().find(|record| record.school.id == school_id))
+            .map(|record| record.school.clone())
+            .ok_or_else(|| ServerFnError::ServerError("Unknown school".to_string()))?;
+        let company = tables.companies.iter()
+            .find(|record| record.company.id == company_id)
+            .map(|record| record.company.clone())
+            .ok_or_else(|| ServerFnError::ServerError("Unknown company".to_string()))?;
+
+        let id = tables.listings.iter().map(|existing| existing.id).max().unwrap_or(0) + 1;
+        tables.listings.push(Listing { user_id: Some(user_id), ..listing.into_listing(id, school, company) });
+        Ok(id)
+    }
+
+    async fn set_listing_hidden(&self, id: i64, hidden: bool) -> Result<(), ServerFnError> {
+        if let Some(listing) = self.tables().listings.iter_mut().find(|listing| listing.id == id) {
+            listing.hidden = hidden;
+        }
+        Ok(())
+    }
+
+    async fn delete_listing(&self, id: i64) -> Result<(), ServerFnError> {
+        let mut tables = self.tables();
+
+        // Takes everything posted on it with it, like ON DELETE CASCADE
+        let comment_ids: Vec<i64> = tables.comments.iter()
+            .filter(|comment| comment.listing_id == id)
+            .map(|comment| comment.id)
+            .collect();
+
+        tables.listings.retain(|listing| listing.id != id);
+        tables.comments.retain(|comment| comment.listing_id != id);
+        tables.votes.retain(|vote| !comment_ids.contains(&vote.comment_id));
+        tables.comment_revisions.retain(|revision| !comment_ids.contains(&revision.comment_id));
+        tables.pseudonyms.retain(|pseudonym| pseudonym.listing_id != id);
+        tables.interview_reports.retain(|report| report.listing_id != id);
+        tables.compensation_reports.retain(|report| report.listing_id != id);
+        tables.reports.retain(|report| report.listing_id != Some(id) && !report.comment_id.map_or(false, |comment_id| comment_ids.contains(&comment_id)));
+        Ok(())
+    }
+
+    async fn get_rating_summary(&self, listing_id: i64) -> Result<Option<RatingSummary>, ServerFnError> {
+        let tables = self.tables();
+
+        if !tables.listings.iter().any(|listing| listing.id == listing_id) {
+            return Ok(None);
+        }
+
+        Ok(Some(tables.ratings(&[listing_id])))
+    }
+}
+
+#[async_trait]
+impl CompanyStore for MemoryStore {
+    async fn get_company(&self, id: i64) -> Result<Option<CompanyDetails>, ServerFnError> {
+        let tables = self.tables();
+
+        let Some(record) = tables.companies.iter().find(|record| record.company.id == id) else {
+            return Ok(None);
+        };
+
+        let listing_ids: Vec<i64> = tables.listings.iter()
+            .filter(|listing| listing.company_id == id && !listing.hidden)
+            .map(|listing| listing.id)
+            .collect();
+
+        Ok(Some(CompanyDetails {
+            company: record.company.clone(),
+            ratings: tables.ratings(&listing_ids),
+            dimensions: DimensionRatings::average(tables.rated_comments(&listing_ids)
+                .map(|comment| &comment.dimensions)),
+        }))
+    }
+
+    async fn find_or_create_company(&self, name: &str) -> Result<Company, ServerFnError> {
+        let mut tables = self.tables();
+        let lowercase = name.to_lowercase();
+
+        let existing = tables.companies.iter()
+            .find(|record| record.company.name.to_lowercase() == lowercase || record.aliases.contains(&lowercase));
+        if let Some(record) = existing {
+            return Ok(record.company.clone());
+        }
+
+        let company = Company {
+            id: tables.companies.iter().map(|record| record.company.id).max().unwrap_or(0) + 1,
+            name: name.to_string(),
+            domain: None,
+            logo_url: None,
+        };
+        tables.companies.push(CompanyRecord { company: company.clone(), aliases: Vec::new() });
+        Ok(company)
+    }
+}
+
+#[async_trait]
+impl SchoolStore for MemoryStore {
+    async fn search_schools(&self, query: &str, limit: i64) -> Result<Vec<School>, ServerFnError> {
+        let query = query.to_lowercase();
+
+        let mut schools: Vec<School> = self.tables().schools.iter()
+            .filter(|record| record.school.name.to_lowercase().contains(&query) || record.aliases.iter().any(|alias| alias.contains(&query)))
+            .map(|record| record.school.clone())
+            .collect();
+        schools.sort_by(|a, b| a.name.cmp(&b.name));
+        schools.truncate(limit as usize);
+
+        Ok(schools)
+    }
+
+    async fn get_school(&self, id: i64) -> Result<Option<School>, ServerFnError> {
+        Ok(self.tables().schools.iter().find(|record| record.school.id == id).map(|record| record.school.clone()))
+    }
+
+    async fn get_school_domains(&self, school_id: i64) -> Result<Vec<String>, ServerFnError> {
+        Ok(self.tables().schools.iter()
+            .find(|record| record.school.id == school_id)
+            .map(|record| record.domains.clone())
+            .unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl CommentStore for MemoryStore {
+    async fn get_comments(&self, listing_id: i64, sort: CommentSort, viewer: Option<i32>) -> Result<Vec<Comment>, ServerFnError> {
+        let tables = self.tables();
+
+        let mut comments: Vec<Comment> = tables.visible_comments(&[listing_id])
+            .map(|comment| tables.load_comment(comment, viewer))
+            .collect();
+
+        // Same orders as the SQL stores, newest first among ties
+        comments.sort_by(|a, b| {
+            let order = match sort {
+                CommentSort::Helpful => b.helpful.cmp(&a.helpful),
+                CommentSort::Newest => b.timestamp.cmp(&a.timestamp),
+                CommentSort::HighestRated => b.rating.total_cmp(&a.rating),
+                CommentSort::LowestRated => a.rating.total_cmp(&b.rating),
+            };
+            order.then(b.id.cmp(&a.id))
+        });
+        Ok(comments)
+    }
+
+    async fn get_comment(&self, id: i64) -> Result<Option<Comment>, ServerFnError> {
+        let tables = self.tables();
+
+        Ok(tables.comments.iter()
+            .find(|comment| comment.id == id)
+            .map(|comment| tables.load_comment(comment, None)))
+    }
+
+    async fn get_review(&self, listing_id: i64, user_id: i32) -> Result<Option<Comment>, ServerFnError> {
+        let tables = self.tables();
+
+        Ok(tables.comments.iter()
+            .find(|comment| comment.listing_id == listing_id && comment.user_id == Some(user_id) && comment.parent_id.is_none())
+            .map(|comment| tables.load_comment(comment, None)))
+    }
+
+    async fn add_comment(&self, mut comment: Comment) -> Result<bool, ServerFnError> {
+        let mut tables = self.tables();
+
+        // One review per user on each listing, like the unique index in the SQL stores
+        let is_review = comment.parent_id.is_none() && comment.user_id.is_some();
+        if is_review && tables.comments.iter().any(|existing| existing.listing_id == comment.listing_id && existing.user_id == comment.user_id && existing.parent_id.is_none()) {
+            return Ok(false);
+        }
+
+        // Comments can be deleted, so the count isn't a free id
+        comment.id = tables.comments.iter().map(|comment| comment.id).max().unwrap_or(0) + 1;
+        let listing_id = comment.listing_id;
+
+        // The first anonymous comment on a listing gives the user the next pseudonym there, which they keep
+        if let (true, Some(user_id)) = (comment.anonymous, comment.user_id) {
+            let on_listing = || tables.pseudonyms.iter().filter(|pseudonym| pseudonym.listing_id == listing_id);
+
+            if !on_listing().any(|pseudonym| pseudonym.user_id == user_id) {
+                let number = on_listing().map(|pseudonym| pseudonym.number).max().unwrap_or(0) + 1;
+                tables.pseudonyms.push(ListingPseudonym { listing_id, user_id, number });
+            }
+        }
+
+        tables.comments.push(comment);
+        tables.refresh_ratings(listing_id);
+        Ok(true)
+    }
+
+    async fn edit_comment(&self, comment: Comment) -> Result<(), ServerFnError> {
+        self.save_edit(comment, false);
+        Ok(())
+    }
+
+    async fn restore_comment(&self, comment: Comment) -> Result<(), ServerFnError> {
+        self.save_edit(comment, true);
+        Ok(())
+    }
+
+    async fn delete_comment(&self, id: i64, deleted_by: i32) -> Result<(), ServerFnError> {
+        let mut tables = self.tables();
+
+        let Some(listing_id) = tables.comments.iter().find(|comment| comment.id == id).map(|comment| comment.listing_id) else {
+            return Ok(());
+        };
+
+        // Blanked rather than removed when others replied, like the SQL stores
+        if tables.comments.iter().any(|comment| comment.parent_id == Some(id)) {
+            if let Some(comment) = tables.comments.iter_mut().find(|comment| comment.id == id) {
+                comment.content = String::new();
+                comment.deleted = true;
+                comment.deleted_by = Some(deleted_by);
+            }
+        } else {
+            tables.comments.retain(|comment| comment.id != id);
+            tables.votes.retain(|vote| vote.comment_id != id);
+            tables.comment_revisions.retain(|revision| revision.comment_id != id);
+            tables.reports.retain(|report| report.comment_id != Some(id));
+        }
+        tables.refresh_ratings(listing_id);
+        Ok(())
+    }
+
+    async fn get_comment_revisions(&self, comment_id: i64) -> Result<Vec<CommentRevision>, ServerFnError> {
+        Ok(self.tables().comment_revisions.iter()
+            .rev()
+            .filter(|revision| revision.comment_id == comment_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn set_comment_hidden(&self, id: i64, hidden: bool) -> Result<(), ServerFnError> {
+        let mut tables = self.tables();
+
+        let Some(comment) = tables.comments.iter_mut().find(|comment| comment.id == id) else {
+            return Ok(());
+        };
+        comment.hidden = hidden;
+
+        let listing_id = comment.listing_id;
+        tables.refresh_ratings(listing_id);
+        Ok(())
+    }
+
+    async fn set_vote(&self, comment_id: i64, user_id: i32, value: i32) -> Result<(), ServerFnError> {
+        let mut tables = self.tables();
+
+        match tables.votes.iter_mut().find(|vote| vote.comment_id == comment_id && vote.user_id == user_id) {
+            Some(vote) => vote.value = value,
+            None => tables.votes.push(CommentVote { comment_id, user_id, value }),
+        }
+        Ok(())
+    }
+
+    async fn remove_vote(&self, comment_id: i64, user_id: i32) -> Result<(), ServerFnError> {
+        self.tables().votes.retain(|vote| !(vote.comment_id == comment_id && vote.user_id == user_id));
+        Ok(())
+    }
+
+    async fn get_dimension_averages(&self, listing_id: i64) -> Result<DimensionRatings, ServerFnError> {
+        let tables = self.tables();
+
+        Ok(DimensionRatings::average(tables.rated_comments(&[listing_id])
+            .map(|comment| &comment.dimensions)))
+    }
+}
+
+#[async_trait]
+impl InterviewStore for MemoryStore {
+    async fn get_interview_reports(&self, listing_id: i64) -> Result<Vec<InterviewReport>, ServerFnError> {
+        Ok(self.tables().interview_reports.iter()
+            .rev()
+            .filter(|report| report.listing_id == listing_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn add_interview_report(&self, mut report: InterviewReport) -> Result<(), ServerFnError> {
+        let mut tables = self.tables();
+        report.id = tables.interview_reports.len() as i64 + 1;
+        tables.interview_reports.push(report);
+        Ok(())
+    }
+
+    async fn get_interview_stats(&self, company_id: i64) -> Result<InterviewStats, ServerFnError> {
+        let tables = self.tables();
+
+        let reports: Vec<&InterviewReport> = tables.interview_reports.iter()
+            .filter(|report| tables.listings.iter().any(|listing| listing.id == report.listing_id && listing.company_id == company_id))
+            .collect();
+
+        let average = |values: Vec<f64>| (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64);
+
+        Ok(InterviewStats {
+            reports: reports.len() as i64,
+            offer_rate: average(reports.iter()
+                .filter(|report| report.outcome != InterviewOutcome::Pending)
+                .map(|report| if report.outcome == InterviewOutcome::Offer { 1.0 } else { 0.0 })
+                .collect()),
+            average_days: average(reports.iter().filter_map(|report| report.process_days).map(f64::from).collect()),
+            average_rounds: average(reports.iter().map(|report| report.rounds as f64).collect()),
+            average_difficulty: average(reports.iter().map(|report| report.difficulty as f64).collect()),
+        })
+    }
+}
+
+#[async_trait]
+impl CompensationStore for MemoryStore {
+    async fn get_compensation_reports(&self, listing_id: i64) -> Result<Vec<CompensationReport>, ServerFnError> {
+        Ok(self.tables().compensation_reports.iter()
+            .filter(|report| report.listing_id == listing_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_company_compensation_reports(&self, company_id: i64) -> Result<Vec<CompensationReport>, ServerFnError> {
+        let tables = self.tables();
+
+        Ok(tables.compensation_reports.iter()
+            .filter(|report| tables.listings.iter().any(|listing| listing.id == report.listing_id && listing.company_id == company_id))
+            .cloned()
+            .collect())
+    }
+
+    async fn add_compensation_report(&self, mut report: CompensationReport) -> Result<(), ServerFnError> {
+        let mut tables = self.tables();
+        report.id = tables.compensation_reports.len() as i64 + 1;
+        tables.compensation_reports.push(report);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ModerationStore for MemoryStore {
+    async fn add_report(&self, mut report: Report) -> Result<(), ServerFnError> {
+        let mut tables = self.tables();
+
+        let duplicate = tables.reports.iter().any(|existing| existing.reporter_id == report.reporter_id
+            && existing.comment_id == report.comment_id && existing.listing_id == report.listing_id);
+        if !duplicate {
+            report.id = tables.reports.iter().map(|existing| existing.id).max().unwrap_or(0) + 1;
+            tables.reports.push(report);
+        }
+        Ok(())
+    }
+
+    async fn get_report(&self, id: i64) -> Result<Option<Report>, ServerFnError> {
+        Ok(self.tables().reports.iter().find(|report| report.id == id).cloned())
+    }
+
+    async fn get_open_reports(&self) -> Result<Vec<Report>, ServerFnError> {
+        let mut reports: Vec<Report> = self.tables().reports.iter()
+            .filter(|report| report.resolved_at.is_none())
+            .cloned()
+            .collect();
+        reports.sort_by_key(|report| (report.timestamp, report.id));
+        Ok(reports)
+    }
+
+    async fn resolve_reports(&self, report: &Report, resolved_at: i64) -> Result<(), ServerFnError> {
+        for existing in self.tables().reports.iter_mut() {
+            let same_content = (report.comment_id.is_some() && existing.comment_id == report.comment_id)
+                || (report.listing_id.is_some() && existing.listing_id == report.listing_id);
+            if same_content && existing.resolved_at.is_none() {
+                existing.resolved_at = Some(resolved_at);
+            }
+        }
+        Ok(())
+    }
+
+    async fn add_moderation_log_entry(&self, mut entry: ModerationLogEntry) -> Result<(), ServerFnError> {
+        let mut tables = self.tables();
+        entry.id = tables.moderation_log.len() as i64 + 1;
+        tables.moderation_log.push(entry);
+        Ok(())
+    }
+
+    async fn get_moderation_log(&self, limit: i64) -> Result<Vec<ModerationLogEntry>, ServerFnError> {
+        let mut entries = self.tables().moderation_log.clone();
+        entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then(b.id.cmp(&a.id)));
+        entries.truncate(limit as usize);
+        Ok(entries)
+    }
+}
+
+#[async_trait]
+impl UserStore for MemoryStore {
+    async fn create_user(&self, user: User) -> Result<(), ServerFnError> {
+        let mut tables = self.tables();
+        if tables.users.iter().any(|existing| existing.id == user.id || existing.name == user.name || (user.email.is_some() && existing.email == user.email)) {
+            return Err(ServerFnError::ServerError(format!("User {} already exists", user.name)));
+        }
+        tables.users.push(user);
+        Ok(())
+    }
+
+    async fn get_user(&self, id: i32) -> Result<Option<User>, ServerFnError> {
+        Ok(self.tables().users.iter().find(|user| user.id == id).cloned())
+    }
+
+    async fn get_user_by_name(&self, name: &str) -> Result<Option<User>, ServerFnError> {
+        Ok(self.tables().users.iter().find(|user| user.name == name).cloned())
+    }
+
+    async fn get_user_by_email(&self, email: &str) -> Result<Option<User>, ServerFnError> {
+        Ok(self.tables().users.iter().find(|user| user.email.as_deref() == Some(email)).cloned())
+    }
+
+    async fn set_email(&self, user_id: i32, email: &str) -> Result<(), ServerFnError> {
+        let mut tables = self.tables();
+        if tables.users.iter().any(|user| user.id != user_id && user.email.as_deref() == Some(email)) {
+            return Err(ServerFnError::ServerError(format!("Email {} is already in use", email)));
+        }
+        if let Some(user) = tables.users.iter_mut().find(|user| user.id == user_id) {
+            user.email = Some(email.to_string());
+            user.verified = false;
+        }
+        Ok(())
+    }
+
+    async fn set_verified(&self, user_id: i32) -> Result<(), ServerFnError> {
+        if let Some(user) = self.tables().users.iter_mut().find(|user| user.id == user_id) {
+            user.verified = true;
+        }
+        Ok(())
+    }
+
+    async fn set_banned(&self, user_id: i32, banned: bool) -> Result<(), ServerFnError> {
+        if let Some(user) = self.tables().users.iter_mut().find(|user| user.id == user_id) {
+            user.banned = banned;
+        }
+        Ok(())
+    }
+
+    async fn set_role(&self, user_id: i32, role: Role) -> Result<(), ServerFnError> {
+        if let Some(user) = self.tables().users.iter_mut().find(|user| user.id == user_id) {
+            user.role = role;
+        }
+        Ok(())
+    }
+
+    async fn get_staff(&self, school_id: Option<i64>) -> Result<Vec<User>, ServerFnError> {
+        let mut users: Vec<User> = self.tables().users.iter()
+            .filter(|user| user.role != Role::User && school_id.map_or(true, |school_id| user.school_id == school_id))
+            .cloned()
+            .collect();
+        users.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(users)
+    }
+
+    async fn create_verification(&self, verification: EmailVerification) -> Result<(), ServerFnError> {
+        self.tables().verifications.push(verification);
+        Ok(())
+    }
+
+    async fn take_verification(&self, token: &str) -> Result<Option<EmailVerification>, ServerFnError> {
+        let mut tables = self.tables();
+        let index = tables.verifications.iter().position(|verification| verification.token == token);
+        Ok(index.map(|index| tables.verifications.remove(index)))
+    }
+}
+
+#[async_trait]
+impl SessionStore for MemoryStore {
+    async fn create_session(&self, session: Session) -> Result<(), ServerFnError> {
+        self.tables().sessions.push(session);
+        Ok(())
+    }
+
+    async fn get_session(&self, user_id: i32, session_token: &str) -> Result<Option<Session>, ServerFnError> {
+        Ok(self.tables().sessions.iter()
+            .find(|session| session.user_id == user_id && session.session_token == session_token)
+            .cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comment::{add_comment, add_reply, delete_comment, edit_comment, get_comments, retract_vote, vote_comment, CommentSort, DimensionRatings, MAX_REPLY_DEPTH};
+    use crate::listing::{add_listing, get_all_listings, ListingFilters, ListingSort, NewListing, Season, WorkMode, LISTINGS_PER_PAGE};
+    use crate::login::{create_user, login_user, validate_session};
+    use crate::moderation::{moderate, report_comment, ModerationAction, ReportReason};
+    use crate::rating::get_rating_summary;
+    use crate::mailer::{DynMailer, Email, Mailer};
+    use crate::session::{get_session, set_session, SessionModel};
+    use crate::verify_email::verify_email;
+
+    // Keeps sent emails so tests can follow the links in them
+    #[derive(Default)]
+    struct Outbox(Mutex<Vec<Email>>);
+
+    #[async_trait]
+    impl Mailer for Outbox {
+        async fn send(&self, email: Email) -> Result<(), ServerFnError> {
+            self.0.lock().unwrap().push(email);
+            Ok(())
+        }
+    }
+
+    // The token from the last verification link sent
+    fn sent_token() -> String {
+        let outbox = use_context::<Arc<Outbox>>().unwrap();
+        let emails = outbox.0.lock().unwrap();
+        let body = &emails.last().unwrap().body;
+        body.split("token=").nth(1).unwrap().split_whitespace().next().unwrap().to_string()
+    }
+
+    // What a request gives server functions: a MemoryStore with only its schools seeded, a mailer, and an actix request to keep the session in.
+    // The context lives in the returned runtime, so dispose of it at the end of the test.
+    async fn serve_from_memory() -> RuntimeId {
+        let store = MemoryStore::default();
+        store.migrate().await.unwrap();
+        let outbox = Arc::new(Outbox::default());
+
+        let runtime = create_runtime();
+        provide_context::<DynStore>(Arc::new(store));
+        provide_context::<DynMailer>(outbox.clone());
+        provide_context(outbox);
+        provide_context(actix_web::test::TestRequest::default().to_http_request());
+        runtime
+    }
+
+    // Registers a user at UCLA and logs them in, verifying their email through the link sent to it
+    async fn register_verified(name: &str) -> SessionModel {
+        let session = create_user(name.to_string(), "hunter2".to_string(), 1, format!("{}@ucla.edu", name)).await.unwrap().session;
+        set_session(session.clone()).await.unwrap();
+        verify_email(sent_token()).await.unwrap().unwrap();
+        session
+    }
+
+    fn user(id: i32, name: &str) -> User {
+        User {
+            id,
+            name: name.to_string(),
+            password: "hash".to_string(),
+            school_id: 1,
+            email: None,
+            verified: false,
+            banned: false,
+            role: Role::User,
+        }
+    }
+
+    fn new_listing(company: &str) -> NewListing {
+        NewListing {
+            company: company.to_string(),
+            position: "Intern".to_string(),
+            description: "Builds things".to_string(),
+            url: "https://example.com".to_string(),
+            school_id: Some(1),
+            location: "Los Angeles".to_string(),
+            work_mode: Some(WorkMode::Onsite),
+            paid: Some(true),
+            hourly_rate: Some(30.0),
+            season: Some(Season::Summer),
+            year: Some(2024),
+            deadline: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn users_are_found_by_name() {
+        let store = MemoryStore::default();
+        store.create_user(user(1, "alice")).await.unwrap();
+        store.create_user(user(2, "bob")).await.unwrap();
+
+        let found = store.get_user_by_name("bob").await.unwrap().unwrap();
+        assert_eq!(found.id, 2);
+        assert!(store.get_user_by_name("carol").await.unwrap().is_none());
+
+        // Names are unique
+        assert!(store.create_user(user(3, "alice")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn registered_users_can_log_in() {
+        let runtime = serve_from_memory().await;
+
+        assert!(create_user("alice".to_string(), "hunter2".to_string(), 0, "alice@ucla.edu".to_string()).await.is_err(), "unknown school");
+        assert!(create_user("alice".to_string(), "hunter2".to_string(), 1, "alice@gmail.com".to_string()).await.is_err(), "not a school email");
+        let registered = create_user("alice".to_string(), "hunter2".to_string(), 1, "Alice@UCLA.edu".to_string()).await.unwrap();
+        let session = login_user("alice".to_string(), "hunter2".to_string()).await.unwrap().unwrap();
+        assert!(registered.can_verify && registered.verification_sent);
+        assert_eq!(session.user_id, registered.session.user_id);
+        assert!(validate_session(session.user_id, session.token.clone()).await.unwrap());
+
+        assert_eq!(login_user("alice".to_string(), "wrong".to_string()).await.unwrap().unwrap_err(), "Incorrect password");
+        assert_eq!(login_user("bob".to_string(), "hunter2".to_string()).await.unwrap().unwrap_err(), "User not found");
+
+        // The session cookie carries the login to later requests
+        set_session(session.clone()).await.unwrap();
+        assert_eq!(get_session().await.unwrap().map(|current| current.token), Some(session.token));
+
+        // The emailed link verifies the address, once
+        let user = store().unwrap().get_user(session.user_id).await.unwrap().unwrap();
+        assert_eq!(user.email.as_deref(), Some("alice@ucla.edu"));
+        assert!(!user.verified);
+        let token = sent_token();
+        verify_email(token.clone()).await.unwrap().unwrap();
+        assert!(store().unwrap().get_user(session.user_id).await.unwrap().unwrap().verified);
+        assert!(verify_email(token).await.unwrap().is_err());
+
+        runtime.dispose();
+    }
+
+    #[tokio::test]
+    async fn students_of_schools_without_domains_register_unverified() {
+        let runtime = serve_from_memory().await;
+        // Like the "Unknown" school the migrations add, it has no email domains
+        let schools = MemoryStore::default();
+        schools.migrate().await.unwrap();
+        schools.tables().schools.push(SchoolRecord {
+            school: School { id: 7, name: "Unknown".to_string() },
+            aliases: Vec::new(),
+            domains: Vec::new(),
+        });
+        provide_context::<DynStore>(Arc::new(schools));
+
+        assert!(create_user("alice".to_string(), "hunter2".to_string(), 7, "alice".to_string()).await.is_err(), "not an email");
+        let registered = create_user("alice".to_string(), "hunter2".to_string(), 7, "Alice@Gmail.com".to_string()).await.unwrap();
+        assert!(!registered.can_verify);
+        assert!(!registered.verification_sent);
+        assert!(use_context::<Arc<Outbox>>().unwrap().0.lock().unwrap().is_empty());
+
+        let user = store().unwrap().get_user(registered.session.user_id).await.unwrap().unwrap();
+        assert_eq!(user.email.as_deref(), Some("alice@gmail.com"));
+        assert!(!user.verified);
+
+        runtime.dispose();
+    }
+
+    #[tokio::test]
+    async fn comments_are_posted_to_their_listing() {
+        let runtime = serve_from_memory().await;
+
+        // Comments and listings can only be posted by the logged in user
+        assert!(add_comment("Great".to_string(), 0.8, DimensionRatings::default(), 1, false).await.unwrap().is_err());
+        let errors = add_listing(new_listing("Acme")).await.unwrap().unwrap_err();
+        assert_eq!(errors.author.as_deref(), Some("You must be logged in to post a listing"));
+        let alice = register_verified("alice").await;
+
+        let listing = new_listing("Acme");
+        let errors = add_listing(NewListing { url: "example.com".to_string(), ..listing.clone() }).await.unwrap().unwrap_err();
+        assert_eq!(errors.url.as_deref(), Some("URL must start with http:// or https://"));
+
+        assert_eq!(add_listing(listing.clone()).await.unwrap().unwrap(), 1);
+        // Posting under the same company name in another case doesn't make a new company
+        let company = store().unwrap().find_or_create_company("acme").await.unwrap();
+        assert_eq!(company.name, "Acme");
+        let school = School { id: 1, name: SEED_SCHOOLS[0].0.to_string() };
+        assert_eq!(get_all_listings(ListingFilters::default(), ListingSort::Newest, None).await.unwrap().listings, vec![Listing { user_id: Some(alice.user_id), ..listing.into_listing(1, school, company) }]);
+
+        let out_of_range = DimensionRatings { learning: Some(1.5), ..DimensionRatings::default() };
+        assert!(add_comment("Great".to_string(), 0.8, out_of_range, 1, false).await.unwrap().is_err());
+        let dimensions = DimensionRatings { mentorship: Some(0.6), ..DimensionRatings::default() };
+        add_comment("Great".to_string(), 0.8, dimensions, 1, false).await.unwrap().unwrap();
+
+        let comments = get_comments(1, CommentSort::Newest).await.unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].get_author(), "alice");
+        assert_eq!(comments[0].get_content(), "Great");
+        assert_eq!(comments[0].dimensions, dimensions);
+        assert!(get_comments(2, CommentSort::Newest).await.unwrap().is_empty());
+
+        runtime.dispose();
+    }
+
+    #[tokio::test]
+    async fn replies_thread_under_their_comment() {
+        let runtime = serve_from_memory().await;
+        register_verified("alice").await;
+        let listing_id = add_listing(new_listing("Acme")).await.unwrap().unwrap();
+        add_comment("Great".to_string(), 0.8, DimensionRatings::default(), listing_id, false).await.unwrap().unwrap();
+
+        // Each reply goes under the last, until the thread is too deep
+        for parent_id in 1..=MAX_REPLY_DEPTH as i64 {
+            add_reply(format!("Reply to {}", parent_id), parent_id, false).await.unwrap().unwrap();
+        }
+        assert!(add_reply("Too deep".to_string(), MAX_REPLY_DEPTH as i64 + 1, false).await.unwrap().is_err());
+        assert!(add_reply("Nothing to reply to".to_string(), 99, false).await.unwrap().is_err());
+
+        let comments = get_comments(listing_id, CommentSort::Newest).await.unwrap();
+        let parents: Vec<(i64, Option<i64>)> = comments.iter().map(|comment| (comment.id, comment.parent_id)).collect();
+        assert_eq!(parents.len(), MAX_REPLY_DEPTH + 1);
+        assert!(parents.contains(&(1, None)));
+        assert!(parents.contains(&(2, Some(1))));
+        assert!(comments.iter().all(|comment| comment.listing_id == listing_id));
+
+        // Replies don't count towards the listing's rating
+        assert_eq!(get_rating_summary(listing_id).await.unwrap().unwrap().count, 1);
+
+        runtime.dispose();
+    }
+
+    #[tokio::test]
+    async fn deleting_a_comment_keeps_its_replies() {
+        let runtime = serve_from_memory().await;
+        let alice = register_verified("alice").await;
+        let listing_id = add_listing(new_listing("Acme")).await.unwrap().unwrap();
+        add_comment("Great".to_string(), 0.9, DimensionRatings::default(), listing_id, false).await.unwrap().unwrap();
+        let bob = register_verified("bob").await;
+        add_reply("Agreed".to_string(), 1, false).await.unwrap().unwrap();
+        assert!(delete_comment(1).await.unwrap().is_err(), "someone else's comment");
+        register_verified("carol").await;
+        add_comment("Meh".to_string(), 0.3, DimensionRatings::default(), listing_id, false).await.unwrap().unwrap();
+
+        delete_comment(3).await.unwrap().unwrap();
+        set_session(alice).await.unwrap();
+        delete_comment(1).await.unwrap().unwrap();
+
+        // The comment with a reply stays, blank, and the one without is gone
+        let comments = get_comments(listing_id, CommentSort::Newest).await.unwrap();
+        assert_eq!(comments.iter().map(|comment| comment.id).collect::<Vec<_>>(), vec![2, 1]);
+        assert!(comments[1].is_deleted());
+        assert!(comments[1].get_content().is_empty());
+        assert_eq!(comments[1].get_author(), "[deleted]");
+        assert_eq!(comments[0].parent_id, Some(1));
+
+        // Neither counts towards the listing's rating
+        assert_eq!(get_rating_summary(listing_id).await.unwrap().unwrap().count, 0);
+
+        // It can't be edited, but its author can post a new review over it
+        assert!(delete_comment(1).await.unwrap().is_err(), "already deleted");
+        assert!(edit_comment(1, "Back".to_string(), 0.7, DimensionRatings::default()).await.unwrap().is_err());
+        add_comment("Back".to_string(), 0.7, DimensionRatings::default(), listing_id, false).await.unwrap().unwrap();
+        let comments = get_comments(listing_id, CommentSort::Newest).await.unwrap();
+        assert!(!comments[1].is_deleted());
+        assert_eq!((comments[1].get_author().as_str(), comments[1].get_content().as_str()), ("alice", "Back"));
+        assert_eq!(get_rating_summary(listing_id).await.unwrap().unwrap().count, 1);
+
+        // Deleted by someone else, like a moderator, it stays deleted
+        store().unwrap().delete_comment(1, bob.user_id).await.unwrap();
+        assert!(edit_comment(1, "Again".to_string(), 0.7, DimensionRatings::default()).await.unwrap().is_err());
+        assert!(add_comment("Again".to_string(), 0.7, DimensionRatings::default(), listing_id, false).await.unwrap().is_err());
+        assert!(get_comments(listing_id, CommentSort::Newest).await.unwrap()[1].is_deleted());
+
+        runtime.dispose();
+    }
+
+    #[tokio::test]
+    async fn comments_deleted_by_a_moderator_stay_down() {
+        let runtime = serve_from_memory().await;
+        let alice = register_verified("alice").await;
+        let listing_id = add_listing(new_listing("Acme")).await.unwrap().unwrap();
+        add_comment("Great".to_string(), 0.9, DimensionRatings::default(), listing_id, false).await.unwrap().unwrap();
+        register_verified("bob").await;
+        add_reply("Agreed".to_string(), 1, false).await.unwrap().unwrap();
+        report_comment(1, ReportReason::Spam, String::new()).await.unwrap().unwrap();
+
+        let carol = register_verified("carol").await;
+        store().unwrap().set_role(carol.user_id, Role::Moderator).await.unwrap();
+        moderate(1, ModerationAction::Delete, String::new()).await.unwrap().unwrap();
+
+        // Having a reply, it's kept but hidden, and its author can't post over it
+        set_session(alice).await.unwrap();
+        assert!(add_comment("Back".to_string(), 0.9, DimensionRatings::default(), listing_id, false).await.unwrap().is_err());
+        let comments = get_comments(listing_id, CommentSort::Newest).await.unwrap();
+        assert_eq!(comments.iter().map(|comment| comment.id).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(get_rating_summary(listing_id).await.unwrap().unwrap().count, 0);
+
+        runtime.dispose();
+    }
+
+    #[tokio::test]
+    async fn helpful_votes_order_comments() {
+        let runtime = serve_from_memory().await;
+        register_verified("alice").await;
+        let listing_id = add_listing(new_listing("Acme")).await.unwrap().unwrap();
+        add_comment("First".to_string(), 0.9, DimensionRatings::default(), listing_id, false).await.unwrap().unwrap();
+        assert!(vote_comment(1, 1).await.unwrap().is_err(), "own comment");
+        register_verified("bob").await;
+        add_comment("Second".to_string(), 0.5, DimensionRatings::default(), listing_id, false).await.unwrap().unwrap();
+
+        register_verified("carol").await;
+        assert!(vote_comment(1, 2).await.unwrap().is_err());
+        vote_comment(1, 1).await.unwrap().unwrap();
+
+        let comments = get_comments(listing_id, CommentSort::Helpful).await.unwrap();
+        assert_eq!(comments.iter().map(|comment| comment.id).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!((comments[0].helpful, comments[0].viewer_vote), (1, 1));
+
+        // Changing the vote replaces it rather than adding another
+        vote_comment(1, -1).await.unwrap().unwrap();
+        let comments = get_comments(listing_id, CommentSort::Helpful).await.unwrap();
+        assert_eq!(comments.iter().map(|comment| comment.id).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!((comments[1].helpful, comments[1].viewer_vote), (-1, -1));
+
+        retract_vote(1).await.unwrap().unwrap();
+        let comments = get_comments(listing_id, CommentSort::Newest).await.unwrap();
+        assert!(comments.iter().all(|comment| comment.helpful == 0 && comment.viewer_vote == 0));
+
+        runtime.dispose();
+    }
+
+    #[tokio::test]
+    async fn posting_again_updates_the_review() {
+        let runtime = serve_from_memory().await;
+        let alice = register_verified("alice").await;
+        let listing_id = add_listing(new_listing("Acme")).await.unwrap().unwrap();
+        add_comment("Great".to_string(), 0.9, DimensionRatings::default(), listing_id, false).await.unwrap().unwrap();
+        add_comment("Better".to_string(), 0.7, DimensionRatings::default(), listing_id, false).await.unwrap().unwrap();
+        assert!(add_comment("Anonymous".to_string(), 0.7, DimensionRatings::default(), listing_id, true).await.unwrap().is_err(), "switched to anonymous");
+
+        let comments = get_comments(listing_id, CommentSort::Newest).await.unwrap();
+        assert_eq!(comments.iter().map(|comment| comment.get_content().as_str()).collect::<Vec<_>>(), vec!["Better"]);
+        assert_eq!(get_rating_summary(listing_id).await.unwrap().unwrap().count, 1);
+
+        // A second review racing the first isn't added
+        let duplicate = Comment { user_id: Some(alice.user_id), ..Comment::new("alice".to_string(), "Again".to_string(), 0, 0.5, listing_id, 0) };
+        assert!(!store().unwrap().add_comment(duplicate).await.unwrap());
+
+        // Once a moderator hides it, it can't be posted over
+        store().unwrap().set_comment_hidden(1, true).await.unwrap();
+        assert!(add_comment("Back".to_string(), 0.9, DimensionRatings::default(), listing_id, false).await.unwrap().is_err());
+        assert_eq!(store().unwrap().get_comment(1).await.unwrap().unwrap().get_content(), "Better");
+
+        runtime.dispose();
+    }
+
+    #[tokio::test]
+    async fn anonymous_comments_keep_a_pseudonym_per_listing() {
+        let runtime = serve_from_memory().await;
+        register_verified("alice").await;
+        let listing_id = add_listing(new_listing("Acme")).await.unwrap().unwrap();
+        add_comment("Great".to_string(), 0.8, DimensionRatings::default(), listing_id, true).await.unwrap().unwrap();
+        add_reply("Also, the food".to_string(), 1, true).await.unwrap().unwrap();
+
+        let comments = get_comments(listing_id, CommentSort::Newest).await.unwrap();
+        assert!(comments.iter().all(|comment| comment.is_own()));
+
+        // Other users see the same pseudonym on both, never the name
+        register_verified("bob").await;
+        let comments = get_comments(listing_id, CommentSort::Newest).await.unwrap();
+        let expected = format!("Verified {} student #1", SEED_SCHOOLS[0].0);
+        assert!(comments.iter().all(|comment| *comment.get_author() == expected && !comment.is_own()), "{:?}", comments.iter().map(|comment| comment.get_author()).collect::<Vec<_>>());
+
+        runtime.dispose();
+    }
+
+    #[tokio::test]
+    async fn listing_pages_follow_the_cursor_without_repeats() {
+        let runtime = serve_from_memory().await;
+        register_verified("alice").await;
+        for index in 0..45 {
+            add_listing(new_listing(&format!("Company {:02}", index % 7))).await.unwrap().unwrap();
+        }
+
+        for sort in [ListingSort::Newest, ListingSort::HighestRated, ListingSort::MostReviewed, ListingSort::Company] {
+            let mut seen = Vec::new();
+            let mut cursor = None;
+            loop {
+                let batch = get_all_listings(ListingFilters::default(), sort, cursor).await.unwrap();
+                assert!(batch.listings.len() as i64 <= LISTINGS_PER_PAGE);
+                seen.extend(batch.listings.iter().map(|listing| listing.id));
+
+                cursor = batch.next_cursor;
+                if cursor.is_none() {
+                    break;
+                }
+            }
+
+            let mut unique = seen.clone();
+            unique.sort();
+            unique.dedup();
+            assert_eq!(seen.len(), 45, "{:?}", sort);
+            assert_eq!(unique.len(), 45, "{:?}", sort);
+        }
+
+        runtime.dispose();
+    }
+}
@@ -0,0 +1,685 @@
+use async_trait::async_trait;
+use leptos::*;
+use sqlx::{Postgres, QueryBuilder, Transaction};
+use sqlx::postgres::PgPool;
+use crate::comment::{Comment, CommentRevision, CommentSort, DimensionRatings};
+use crate::school::School;
+use crate::rating::{RatingSummary, PRIOR_RATING};
+use crate::compensation::CompensationReport;
+use crate::interview::{InterviewReport, InterviewStats};
+use crate::company::{Company, CompanyDetails};
+use crate::db::run_migrations;
+use crate::listing::{Listing, ListingCursor, ListingFilters, ListingSort, NewListing};
+use super::*;
+
+// Production storage, backed by the shared Postgres pool.
+//
+// Queries are checked at runtime rather than with the query! macros, so building doesn't need a Postgres server.
+pub struct PgStore {
+    pool: PgPool,
+}
+
+impl PgStore {
+    pub fn new(pool: PgPool) -> Self {
+        PgStore { pool }
+    }
+
+    // Recomputes the ratings stored on the listing and its company from their comments, leaving out hidden and deleted ones
+    async fn refresh_ratings(&self, tx: &mut Transaction<'_, Postgres>, listing_id: i64) -> Result<(), ServerFnError> {
+        for statement in refresh_ratings_sql("id = $1") {
+            sqlx::query(&statement)
+                .bind(listing_id)
+                .execute(&mut **tx).await?;
+        }
+        Ok(())
+    }
+
+    // Saves the comment's new content, ratings and edited_at, keeping the version it replaces as a revision.
+    // Edits only apply to comments that aren't deleted, and restoring only to comments their own author deleted. Checking
+    // that in the same statements means a comment deleted in the meantime, or by a moderator, stays deleted.
+    async fn save_edit(&self, comment: Comment, restore: bool) -> Result<(), ServerFnError> {
+        let (condition, undelete) = if restore {
+            ("deleted AND deleted_by = user_id", ", deleted = FALSE, deleted_by = NULL")
+        } else {
+            ("NOT deleted", "")
+        };
+        let dimensions = comment.dimensions;
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(&format!("INSERT INTO comment_revisions (comment_id, content, rating, mentorship, compensation, work_life_balance, learning, return_offer, timestamp)
+            SELECT id, content, rating, mentorship, compensation, work_life_balance, learning, return_offer, COALESCE(edited_at, timestamp)
+            FROM comments WHERE id = $1 AND {condition}"))
+            .bind(comment.id)
+            .execute(&mut *tx).await?;
+
+        sqlx::query(&format!("UPDATE comments SET content = $2, rating = $3, mentorship = $4, compensation = $5, work_life_balance = $6, learning = $7, return_offer = $8, edited_at = $9{undelete}
+            WHERE id = $1 AND {condition}"))
+            .bind(comment.id)
+            .bind(comment.content)
+            .bind(comment.rating)
+            .bind(dimensions.mentorship)
+            .bind(dimensions.compensation)
+            .bind(dimensions.work_life_balance)
+            .bind(dimensions.learning)
+            .bind(dimensions.return_offer)
+            .bind(comment.edited_at)
+            .execute(&mut *tx).await?;
+
+        self.refresh_ratings(&mut tx, comment.listing_id).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Store for PgStore {
+    async fn migrate(&self) -> Result<(), ServerFnError> {
+        run_migrations(&self.pool).await?;
+
+        // The stored ratings depend on constants like PRIOR_RATING, so they're recomputed here rather than in the migrations
+        let mut tx = self.pool.begin().await?;
+        for statement in refresh_ratings_sql("TRUE") {
+            sqlx::query(&statement).execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ListingStore for PgStore {
+    async fn list_listings(&self, filters: &ListingFilters, sort: ListingSort, after: Option<&ListingCursor>, limit: i64) -> Result<Vec<(Listing, ListingCursor)>, ServerFnError> {
+        let mut query = list_listings_query(filters, sort, after, limit, "ILIKE");
+        let listings = query.build_query_as::<Listing>().fetch_all(&self.pool).await?;
+        Ok(with_cursors(listings))
+    }
+
+    async fn get_listing(&self, id: i64) -> Result<Option<Listing>, ServerFnError> {
+        let listing = sqlx::query_as::<_, Listing>(&format!("SELECT {LISTING_COLUMNS} FROM listings l WHERE l.id = $1"))
+            .bind(id)
+            .fetch_optional(&self.pool).await?;
+        Ok(listing)
+    }
+
+    async fn search_listings(&self, query: &str, filters: &ListingFilters, limit: i64, offset: i64) -> Result<Vec<Listing>, ServerFnError> {
+        let mut builder = QueryBuilder::new(format!("SELECT {LISTING_COLUMNS} FROM listings l WHERE NOT l.hidden"));
+
+        if !query.is_empty() {
+            builder.push(" AND l.search @@ websearch_to_tsquery('english', ").push_bind(query.to_string()).push(")");
+        }
+        push_filters(&mut builder, filters, "ILIKE");
+
+        builder.push(" ORDER BY ");
+        if !query.is_empty() {
+            builder.push("ts_rank(l.search, websearch_to_tsquery('english', ").push_bind(query.to_string()).push(")) DESC, ");
+        }
+        builder.push("l.id DESC LIMIT ").push_bind(limit).push(" OFFSET ").push_bind(offset);
+
+        let listings = builder.build_query_as::<Listing>().fetch_all(&self.pool).await?;
+        Ok(listings)
+    }
+
+    async fn add_listing(&self, listing: NewListing, company_id: i64, user_id: i32) -> Result<i64, ServerFnError> {
+        let id = sqlx::query_scalar::<_, i64>("INSERT INTO listings (company_id, company, position, description, url, school_id, school, location, work_mode, paid, hourly_rate, season, year, deadline, user_id, rating_score)
+            VALUES ($1, (SELECT name FROM companies WHERE id = $1), $2, $3, $4, $5, (SELECT name FROM schools WHERE id = $5), $6, $7, $8, $9, $10, $11, $12, $13, $14) RETURNING id")
+            .bind(company_id)
+            .bind(listing.position)
+            .bind(listing.description)
+            .bind(listing.url)
+            .bind(listing.school_id)
+            .bind(listing.location)
+            .bind(listing.work_mode)
+            .bind(listing.paid)
+            .bind(listing.hourly_rate)
+            .bind(listing.season)
+            .bind(listing.year)
+            .bind(listing.deadline)
+            .bind(user_id)
+            // Without reviews the score is the prior
+            .bind(PRIOR_RATING)
+            .fetch_one(&self.pool).await?;
+        Ok(id)
+    }
+
+    async fn set_listing_hidden(&self, id: i64, hidden: bool) -> Result<(), ServerFnError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("UPDATE listings SET hidden = $2 WHERE id = $1")
+            .bind(id)
+            .bind(hidden)
+            .execute(&mut *tx).await?;
+
+        self.refresh_ratings(&mut tx, id).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn delete_listing(&self, id: i64) -> Result<(), ServerFnError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Hiding it first takes it out of its company's ratings while the company can still be found from it
+        sqlx::query("UPDATE listings SET hidden = TRUE WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx).await?;
+        self.refresh_ratings(&mut tx, id).await?;
+
+        sqlx::query("DELETE FROM listings WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_rating_summary(&self, listing_id: i64) -> Result<Option<RatingSummary>, ServerFnError> {
+        let ratings = sqlx::query_as::<_, RatingAggregate>(&format!("SELECT {RATING_COLUMNS} FROM listings WHERE id = $1"))
+            .bind(listing_id)
+            .fetch_optional(&self.pool).await?;
+        Ok(ratings.map(RatingAggregate::into_summary))
+    }
+}
+
+#[async_trait]
+impl CompanyStore for PgStore {
+    async fn get_company(&self, id: i64) -> Result<Option<CompanyDetails>, ServerFnError> {
+        let company = sqlx::query_as::<_, CompanyRow>(&format!(
+            "SELECT co.id, co.name, co.domain, co.logo_url, co.rating_average, co.rating_count,
+                co.stars_1, co.stars_2, co.stars_3, co.stars_4, co.stars_5, {DIMENSION_AVERAGES}
+            FROM companies co
+            LEFT JOIN listings l ON l.company_id = co.id AND NOT l.hidden
+            LEFT JOIN comments c ON c.listing_id = l.id AND NOT c.hidden AND NOT c.deleted
+            WHERE co.id = $1
+            GROUP BY co.id"))
+            .bind(id)
+            .fetch_optional(&self.pool).await?;
+        Ok(company.map(CompanyRow::into_details))
+    }
+
+    async fn find_or_create_company(&self, name: &str) -> Result<Company, ServerFnError> {
+        let existing = sqlx::query_as::<_, Company>("SELECT co.id, co.name, co.domain, co.logo_url FROM companies co
+            WHERE LOWER(co.name) = LOWER($1) OR co.id IN (SELECT company_id FROM company_aliases WHERE alias = LOWER($1))
+            LIMIT 1")
+            .bind(name)
+            .fetch_optional(&self.pool).await?;
+
+        if let Some(company) = existing {
+            return Ok(company);
+        }
+
+        // Another request may have added it since, in which case that one is returned
+        let company = sqlx::query_as::<_, Company>("INSERT INTO companies (name) VALUES ($1)
+            ON CONFLICT ((LOWER(name))) DO UPDATE SET name = companies.name
+            RETURNING id, name, domain, logo_url")
+            .bind(name)
+            .fetch_one(&self.pool).await?;
+        Ok(company)
+    }
+}
+
+#[async_trait]
+impl SchoolStore for PgStore {
+    async fn search_schools(&self, query: &str, limit: i64) -> Result<Vec<School>, ServerFnError> {
+        let pattern = format!("%{}%", escape_like(query));
+        let schools = sqlx::query_as::<_, School>("SELECT DISTINCT s.id, s.name FROM schools s LEFT JOIN school_aliases a ON a.school_id = s.id
+            WHERE s.name ILIKE $1 OR a.alias ILIKE $1
+            ORDER BY s.name LIMIT $2")
+            .bind(pattern)
+            .bind(limit)
+            .fetch_all(&self.pool).await?;
+        Ok(schools)
+    }
+
+    async fn get_school(&self, id: i64) -> Result<Option<School>, ServerFnError> {
+        let school = sqlx::query_as::<_, School>("SELECT id, name FROM schools WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool).await?;
+        Ok(school)
+    }
+
+    async fn get_school_domains(&self, school_id: i64) -> Result<Vec<String>, ServerFnError> {
+        let domains = sqlx::query_scalar::<_, String>("SELECT domain FROM school_domains WHERE school_id = $1")
+            .bind(school_id)
+            .fetch_all(&self.pool).await?;
+        Ok(domains)
+    }
+}
+
+#[async_trait]
+impl CommentStore for PgStore {
+    async fn get_comments(&self, listing_id: i64, sort: CommentSort, viewer: Option<i32>) -> Result<Vec<Comment>, ServerFnError> {
+        let order = match sort {
+            CommentSort::Helpful => "helpful DESC",
+            CommentSort::Newest => "c.timestamp DESC",
+            CommentSort::HighestRated => "c.rating DESC",
+            CommentSort::LowestRated => "c.rating ASC",
+        };
+
+        let comments = sqlx::query_as::<_, Comment>(
+            &format!("SELECT {COMMENT_COLUMNS},
+                COALESCE((SELECT v.value FROM comment_votes v WHERE v.comment_id = c.id AND v.user_id = $2), 0) AS viewer_vote
+            FROM {COMMENT_TABLES}
+            WHERE c.listing_id = $1 AND NOT c.hidden
+            ORDER BY {order}, c.id DESC"))
+            .bind(listing_id)
+            .bind(viewer)
+            .fetch_all(&self.pool).await?;
+        Ok(comments)
+    }
+
+    async fn get_comment(&self, id: i64) -> Result<Option<Comment>, ServerFnError> {
+        let comment = sqlx::query_as::<_, Comment>(
+            &format!("SELECT {COMMENT_COLUMNS}, 0 AS viewer_vote
+            FROM {COMMENT_TABLES}
+            WHERE c.id = $1"))
+            .bind(id)
+            .fetch_optional(&self.pool).await?;
+        Ok(comment)
+    }
+
+    async fn get_review(&self, listing_id: i64, user_id: i32) -> Result<Option<Comment>, ServerFnError> {
+        let comment = sqlx::query_as::<_, Comment>(
+            &format!("SELECT {COMMENT_COLUMNS}, 0 AS viewer_vote
+            FROM {COMMENT_TABLES}
+            WHERE c.listing_id = $1 AND c.user_id = $2 AND c.parent_id IS NULL"))
+            .bind(listing_id)
+            .bind(user_id)
+            .fetch_optional(&self.pool).await?;
+        Ok(comment)
+    }
+
+    async fn add_comment(&self, comment: Comment) -> Result<bool, ServerFnError> {
+        let listing_id = comment.listing_id;
+        let dimensions = comment.dimensions;
+        let mut tx = self.pool.begin().await?;
+
+        // Two requests can both find no review by the user and both try to add one, so the second is left to do nothing
+        let added = sqlx::query("INSERT INTO comments (author, content, timestamp, rating, listing_id, mentorship, compensation, work_life_balance, learning, return_offer, parent_id, user_id, anonymous)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            ON CONFLICT (listing_id, user_id) WHERE parent_id IS NULL DO NOTHING")
+            .bind(comment.author)
+            .bind(comment.content)
+            .bind(comment.timestamp)
+            .bind(comment.rating)
+            .bind(comment.listing_id)
+            .bind(dimensions.mentorship)
+            .bind(dimensions.compensation)
+            .bind(dimensions.work_life_balance)
+            .bind(dimensions.learning)
+            .bind(dimensions.return_offer)
+            .bind(comment.parent_id)
+            .bind(comment.user_id)
+            .bind(comment.anonymous)
+            .execute(&mut *tx).await?
+            .rows_affected() > 0;
+
+        if !added {
+            return Ok(false);
+        }
+
+        // The first anonymous comment on a listing gives the user the next pseudonym there, which they keep
+        if let (true, Some(user_id)) = (comment.anonymous, comment.user_id) {
+            // Locks the listing first so concurrent first comments take the next number one at a time, rather than both
+            // reading the same MAX and one failing on UNIQUE (listing_id, number). NO KEY UPDATE doesn't wait on the
+            // KEY SHARE lock the comment's foreign key already holds, so two of these can't deadlock.
+            sqlx::query("SELECT id FROM listings WHERE id = $1 FOR NO KEY UPDATE")
+                .bind(listing_id)
+                .execute(&mut *tx).await?;
+
+            sqlx::query("INSERT INTO listing_pseudonyms (listing_id, user_id, number)
+                SELECT $1, $2, COALESCE(MAX(number), 0) + 1 FROM listing_pseudonyms WHERE listing_id = $1
+                ON CONFLICT (listing_id, user_id) DO NOTHING")
+                .bind(listing_id)
+                .bind(user_id)
+                .execute(&mut *tx).await?;
+        }
+
+        self.refresh_ratings(&mut tx, listing_id).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn edit_comment(&self, comment: Comment) -> Result<(), ServerFnError> {
+        self.save_edit(comment, false).await
+    }
+
+    async fn restore_comment(&self, comment: Comment) -> Result<(), ServerFnError> {
+        self.save_edit(comment, true).await
+    }
+
+    async fn delete_comment(&self, id: i64, deleted_by: i32) -> Result<(), ServerFnError> {
+        let mut tx = self.pool.begin().await?;
+
+        // A comment others replied to is blanked rather than removed, so the replies keep their place
+        let mut listing_id = sqlx::query_scalar::<_, i64>("UPDATE comments SET content = '', deleted = TRUE, deleted_by = $2
+            WHERE id = $1 AND EXISTS (SELECT 1 FROM comments r WHERE r.parent_id = comments.id)
+            RETURNING listing_id")
+            .bind(id)
+            .bind(deleted_by)
+            .fetch_optional(&mut *tx).await?;
+
+        // Otherwise its votes, revisions and reports go with it through ON DELETE CASCADE
+        if listing_id.is_none() {
+            listing_id = sqlx::query_scalar::<_, i64>("DELETE FROM comments WHERE id = $1 RETURNING listing_id")
+                .bind(id)
+                .fetch_optional(&mut *tx).await?;
+        }
+
+        if let Some(listing_id) = listing_id {
+            self.refresh_ratings(&mut tx, listing_id).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_comment_revisions(&self, comment_id: i64) -> Result<Vec<CommentRevision>, ServerFnError> {
+        let revisions = sqlx::query_as::<_, CommentRevision>(
+            "SELECT id, comment_id, content, rating, mentorship, compensation, work_life_balance, learning, return_offer, timestamp
+            FROM comment_revisions WHERE comment_id = $1
+            ORDER BY timestamp DESC, id DESC")
+            .bind(comment_id)
+            .fetch_all(&self.pool).await?;
+        Ok(revisions)
+    }
+
+    async fn set_comment_hidden(&self, id: i64, hidden: bool) -> Result<(), ServerFnError> {
+        let mut tx = self.pool.begin().await?;
+
+        let listing_id = sqlx::query_scalar::<_, i64>("UPDATE comments SET hidden = $2 WHERE id = $1 RETURNING listing_id")
+            .bind(id)
+            .bind(hidden)
+            .fetch_optional(&mut *tx).await?;
+
+        if let Some(listing_id) = listing_id {
+            self.refresh_ratings(&mut tx, listing_id).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn set_vote(&self, comment_id: i64, user_id: i32, value: i32) -> Result<(), ServerFnError> {
+        sqlx::query("INSERT INTO comment_votes (comment_id, user_id, value) VALUES ($1, $2, $3)
+            ON CONFLICT (comment_id, user_id) DO UPDATE SET value = EXCLUDED.value")
+            .bind(comment_id)
+            .bind(user_id)
+            .bind(value)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn remove_vote(&self, comment_id: i64, user_id: i32) -> Result<(), ServerFnError> {
+        sqlx::query("DELETE FROM comment_votes WHERE comment_id = $1 AND user_id = $2")
+            .bind(comment_id)
+            .bind(user_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_dimension_averages(&self, listing_id: i64) -> Result<DimensionRatings, ServerFnError> {
+        let averages = sqlx::query_as::<_, DimensionRatings>(&format!(
+            "SELECT {DIMENSION_AVERAGES} FROM comments c WHERE c.listing_id = $1 AND NOT c.hidden AND NOT c.deleted"))
+            .bind(listing_id)
+            .fetch_one(&self.pool).await?;
+        Ok(averages)
+    }
+}
+
+#[async_trait]
+impl InterviewStore for PgStore {
+    async fn get_interview_reports(&self, listing_id: i64) -> Result<Vec<InterviewReport>, ServerFnError> {
+        let reports = sqlx::query_as::<_, InterviewReport>("SELECT id, listing_id, author, timestamp, rounds, online_assessment, phone_screen, onsite, questions, difficulty, process_days,
+                outcome
+            FROM interview_reports WHERE listing_id = $1
+            ORDER BY id DESC")
+            .bind(listing_id)
+            .fetch_all(&self.pool).await?;
+        Ok(reports)
+    }
+
+    async fn add_interview_report(&self, report: InterviewReport) -> Result<(), ServerFnError> {
+        sqlx::query("INSERT INTO interview_reports (listing_id, author, timestamp, rounds, online_assessment, phone_screen, onsite, questions, difficulty, process_days, outcome)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)")
+            .bind(report.listing_id)
+            .bind(report.author)
+            .bind(report.timestamp)
+            .bind(report.rounds)
+            .bind(report.online_assessment)
+            .bind(report.phone_screen)
+            .bind(report.onsite)
+            .bind(report.questions)
+            .bind(report.difficulty)
+            .bind(report.process_days)
+            .bind(report.outcome)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_interview_stats(&self, company_id: i64) -> Result<InterviewStats, ServerFnError> {
+        let stats = sqlx::query_as::<_, InterviewStats>("SELECT COUNT(r.id) AS reports,
+                AVG(CASE WHEN r.outcome = 'offer' THEN 1 WHEN r.outcome <> 'pending' THEN 0 END)::DOUBLE PRECISION AS offer_rate,
+                AVG(r.process_days)::DOUBLE PRECISION AS average_days,
+                AVG(r.rounds)::DOUBLE PRECISION AS average_rounds,
+                AVG(r.difficulty)::DOUBLE PRECISION AS average_difficulty
+            FROM interview_reports r JOIN listings l ON l.id = r.listing_id
+            WHERE l.company_id = $1")
+            .bind(company_id)
+            .fetch_one(&self.pool).await?;
+        Ok(stats)
+    }
+}
+
+#[async_trait]
+impl CompensationStore for PgStore {
+    async fn get_compensation_reports(&self, listing_id: i64) -> Result<Vec<CompensationReport>, ServerFnError> {
+        let reports = sqlx::query_as::<_, CompensationReport>("SELECT id, listing_id, author, timestamp, pay, pay_period, housing_stipend, relocation, signing_bonus, year, location
+            FROM compensation_reports WHERE listing_id = $1")
+            .bind(listing_id)
+            .fetch_all(&self.pool).await?;
+        Ok(reports)
+    }
+
+    async fn get_company_compensation_reports(&self, company_id: i64) -> Result<Vec<CompensationReport>, ServerFnError> {
+        let reports = sqlx::query_as::<_, CompensationReport>("SELECT r.id, r.listing_id, r.author, r.timestamp, r.pay, r.pay_period, r.housing_stipend, r.relocation,
+                r.signing_bonus, r.year, r.location
+            FROM compensation_reports r JOIN listings l ON l.id = r.listing_id
+            WHERE l.company_id = $1")
+            .bind(company_id)
+            .fetch_all(&self.pool).await?;
+        Ok(reports)
+    }
+
+    async fn add_compensation_report(&self, report: CompensationReport) -> Result<(), ServerFnError> {
+        sqlx::query("INSERT INTO compensation_reports (listing_id, author, timestamp, pay, pay_period, housing_stipend, relocation, signing_bonus, year, location)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)")
+            .bind(report.listing_id)
+            .bind(report.author)
+            .bind(report.timestamp)
+            .bind(report.pay)
+            .bind(report.pay_period)
+            .bind(report.housing_stipend)
+            .bind(report.relocation)
+            .bind(report.signing_bonus)
+            .bind(report.year)
+            .bind(report.location)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ModerationStore for PgStore {
+    async fn add_report(&self, report: Report) -> Result<(), ServerFnError> {
+        sqlx::query("INSERT INTO reports (reporter_id, comment_id, listing_id, reason, details, timestamp) VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT DO NOTHING")
+            .bind(report.reporter_id)
+            .bind(report.comment_id)
+            .bind(report.listing_id)
+            .bind(report.reason)
+            .bind(report.details)
+            .bind(report.timestamp)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_report(&self, id: i64) -> Result<Option<Report>, ServerFnError> {
+        let report = sqlx::query_as::<_, Report>("SELECT id, reporter_id, comment_id, listing_id, reason, details, timestamp, resolved_at
+            FROM reports WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool).await?;
+        Ok(report)
+    }
+
+    async fn get_open_reports(&self) -> Result<Vec<Report>, ServerFnError> {
+        let reports = sqlx::query_as::<_, Report>("SELECT id, reporter_id, comment_id, listing_id, reason, details, timestamp, resolved_at
+            FROM reports WHERE resolved_at IS NULL
+            ORDER BY timestamp, id")
+            .fetch_all(&self.pool).await?;
+        Ok(reports)
+    }
+
+    async fn resolve_reports(&self, report: &Report, resolved_at: i64) -> Result<(), ServerFnError> {
+        sqlx::query("UPDATE reports SET resolved_at = $1 WHERE resolved_at IS NULL AND (comment_id = $2 OR listing_id = $3)")
+            .bind(resolved_at)
+            .bind(report.comment_id)
+            .bind(report.listing_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn add_moderation_log_entry(&self, entry: ModerationLogEntry) -> Result<(), ServerFnError> {
+        sqlx::query("INSERT INTO moderation_log (moderator_id, action, report_id, comment_id, listing_id, user_id, note, timestamp) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)")
+            .bind(entry.moderator_id)
+            .bind(entry.action)
+            .bind(entry.report_id)
+            .bind(entry.comment_id)
+            .bind(entry.listing_id)
+            .bind(entry.user_id)
+            .bind(entry.note)
+            .bind(entry.timestamp)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_moderation_log(&self, limit: i64) -> Result<Vec<ModerationLogEntry>, ServerFnError> {
+        let entries = sqlx::query_as::<_, ModerationLogEntry>("SELECT id, moderator_id, action, report_id, comment_id, listing_id, user_id, note, timestamp
+            FROM moderation_log
+            ORDER BY timestamp DESC, id DESC
+            LIMIT $1")
+            .bind(limit)
+            .fetch_all(&self.pool).await?;
+        Ok(entries)
+    }
+}
+
+#[async_trait]
+impl UserStore for PgStore {
+    async fn create_user(&self, user: User) -> Result<(), ServerFnError> {
+        sqlx::query("INSERT INTO users (id, name, password, school_id, email, verified, banned, role) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)")
+            .bind(user.id)
+            .bind(user.name)
+            .bind(user.password)
+            .bind(user.school_id)
+            .bind(user.email)
+            .bind(user.verified)
+            .bind(user.banned)
+            .bind(user.role)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_user(&self, id: i32) -> Result<Option<User>, ServerFnError> {
+        let user = sqlx::query_as::<_, User>("SELECT id, name, password, school_id, email, verified, banned, role FROM users WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool).await?;
+        Ok(user)
+    }
+
+    async fn get_user_by_name(&self, name: &str) -> Result<Option<User>, ServerFnError> {
+        let user = sqlx::query_as::<_, User>("SELECT id, name, password, school_id, email, verified, banned, role FROM users WHERE name = $1")
+            .bind(name)
+            .fetch_optional(&self.pool).await?;
+        Ok(user)
+    }
+
+    async fn get_user_by_email(&self, email: &str) -> Result<Option<User>, ServerFnError> {
+        let user = sqlx::query_as::<_, User>("SELECT id, name, password, school_id, email, verified, banned, role FROM users WHERE email = $1")
+            .bind(email)
+            .fetch_optional(&self.pool).await?;
+        Ok(user)
+    }
+
+    async fn set_email(&self, user_id: i32, email: &str) -> Result<(), ServerFnError> {
+        sqlx::query("UPDATE users SET email = $2, verified = FALSE WHERE id = $1")
+            .bind(user_id)
+            .bind(email)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn set_verified(&self, user_id: i32) -> Result<(), ServerFnError> {
+        sqlx::query("UPDATE users SET verified = TRUE WHERE id = $1")
+            .bind(user_id)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn set_banned(&self, user_id: i32, banned: bool) -> Result<(), ServerFnError> {
+        sqlx::query("UPDATE users SET banned = $2 WHERE id = $1")
+            .bind(user_id)
+            .bind(banned)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn set_role(&self, user_id: i32, role: Role) -> Result<(), ServerFnError> {
+        sqlx::query("UPDATE users SET role = $2 WHERE id = $1")
+            .bind(user_id)
+            .bind(role)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_staff(&self, school_id: Option<i64>) -> Result<Vec<User>, ServerFnError> {
+        let users = sqlx::query_as::<_, User>("SELECT id, name, password, school_id, email, verified, banned, role FROM users
+            WHERE role <> 'user' AND ($1::BIGINT IS NULL OR school_id = $1)
+            ORDER BY name")
+            .bind(school_id)
+            .fetch_all(&self.pool).await?;
+        Ok(users)
+    }
+
+    async fn create_verification(&self, verification: EmailVerification) -> Result<(), ServerFnError> {
+        sqlx::query("INSERT INTO email_verifications (token, user_id, email, expiry_date) VALUES ($1, $2, $3, $4)")
+            .bind(verification.token)
+            .bind(verification.user_id)
+            .bind(verification.email)
+            .bind(verification.expiry_date)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn take_verification(&self, token: &str) -> Result<Option<EmailVerification>, ServerFnError> {
+        let verification = sqlx::query_as::<_, EmailVerification>("DELETE FROM email_verifications WHERE token = $1 RETURNING token, user_id, email, expiry_date")
+            .bind(token)
+            .fetch_optional(&self.pool).await?;
+        Ok(verification)
+    }
+}
+
+#[async_trait]
+impl SessionStore for PgStore {
+    async fn create_session(&self, session: Session) -> Result<(), ServerFnError> {
+        sqlx::query("INSERT INTO sessions (session_token, user_id, expiry_date) VALUES ($1, $2, $3)")
+            .bind(session.session_token)
+            .bind(session.user_id)
+            .bind(session.expiry_date)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get_session(&self, user_id: i32, session_token: &str) -> Result<Option<Session>, ServerFnError> {
+        let session = sqlx::query_as::<_, Session>("SELECT session_token, user_id, expiry_date FROM sessions WHERE user_id = $1 AND session_token = $2")
+            .bind(user_id)
+            .bind(session_token)
+            .fetch_optional(&self.pool).await?;
+        Ok(session)
+    }
+}
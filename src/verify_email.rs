@@ -0,0 +1,176 @@
+use leptos::*;
+use leptos::leptos_dom::logging::console_log;
+use leptos_meta::Title;
+use leptos_router::*;
+use cfg_if::cfg_if;
+
+cfg_if! {
+	if #[cfg(feature = "ssr")] {
+		use crate::store::{store, EmailVerification};
+		use crate::mailer::{mailer, site_url, Email};
+		use crate::login::current_user;
+
+		// Checks that the email looks like an address, and returns it lowercase
+		pub fn check_email(email: &str) -> Result<String, String> {
+			let email = email.trim().to_lowercase();
+
+			match email.split_once('@') {
+				Some((name, domain)) if !name.is_empty() && !domain.contains('@') && domain.contains('.') => Ok(email),
+				_ => Err("Enter a valid email address".to_string()),
+			}
+		}
+
+		// Checks that the email is an address at one of the school's domains, and returns it lowercase
+		pub async fn check_school_email(email: &str, school_id: i64) -> Result<Result<String, String>, ServerFnError> {
+			let email = match check_email(email) {
+				Ok(email) => email,
+				Err(e) => return Ok(Err(e)),
+			};
+			let domain = email.split_once('@').map(|(_, domain)| domain).unwrap_or_default();
+
+			let domains = store()?.get_school_domains(school_id).await?;
+
+			if domains.is_empty() {
+				return Ok(Err("School emails can't be verified for this school yet".to_string()));
+			}
+
+			// Subdomains count too, e.g. g.ucla.edu for ucla.edu
+			if !domains.iter().any(|school_domain| domain == school_domain || domain.ends_with(&format!(".{}", school_domain))) {
+				return Ok(Err(format!("Use your school email, ending in {}", domains.join(" or "))));
+			}
+
+			Ok(Ok(email))
+		}
+
+		// Emails a one-time link that verifies the address. It expires after a day.
+		pub async fn send_verification(user_id: i32, email: &str) -> Result<(), ServerFnError> {
+			let token = u128::from_le_bytes(rand::random::<[u8; 16]>()).to_string();
+			let expiry_date = (chrono::Utc::now() + chrono::Duration::hours(24)).timestamp_millis();
+
+			store()?.create_verification(EmailVerification {
+				token: token.clone(),
+				user_id,
+				email: email.to_string(),
+				expiry_date,
+			}).await?;
+
+			mailer()?.send(Email {
+				to: email.to_string(),
+				subject: "Verify your Interniverse account".to_string(),
+				body: format!("Open this link to verify your school email:\n\n{}/verify-email?token={}\n\nThe link expires in 24 hours.", site_url(), token),
+			}).await
+		}
+	}
+}
+
+// Sends the logged in user a new verification link, changing their email first if it's a different one
+#[server(SendVerification, "/send-verification")]
+pub async fn send_verification_email(email: String) -> Result<Result<(), String>, ServerFnError> {
+	let Some(user) = current_user().await? else {
+		return Ok(Err("You must be logged in to verify your email".to_string()));
+	};
+
+	let email = match check_school_email(&email, user.school_id).await? {
+		Ok(email) => email,
+		Err(e) => return Ok(Err(e)),
+	};
+
+	if user.email.as_deref() != Some(email.as_str()) {
+		if store()?.get_user_by_email(&email).await?.is_some() {
+			return Ok(Err("That email is already in use".to_string()));
+		}
+
+		store()?.set_email(user.id, &email).await?;
+	} else if user.verified {
+		return Ok(Err("Your email is already verified".to_string()));
+	}
+
+	send_verification(user.id, &email).await?;
+	Ok(Ok(()))
+}
+
+#[server(VerifyEmail, "/verify-email")]
+pub async fn verify_email(token: String) -> Result<Result<(), String>, ServerFnError> {
+	let invalid = || Ok(Err("This link is invalid or has already been used".to_string()));
+
+	let Some(verification) = store()?.take_verification(&token).await? else {
+		return invalid();
+	};
+
+	if verification.expiry_date < chrono::Utc::now().timestamp_millis() {
+		return Ok(Err("This link has expired. Send a new one from your profile.".to_string()));
+	}
+
+	let Some(user) = store()?.get_user(verification.user_id).await? else {
+		return invalid();
+	};
+
+	// A link sent to an address the user has since changed doesn't verify the new one
+	if user.email.as_deref() != Some(verification.email.as_str()) {
+		return invalid();
+	}
+
+	store()?.set_verified(user.id).await?;
+	Ok(Ok(()))
+}
+
+#[derive(Params, PartialEq, Clone)]
+struct VerifyEmailQuery {
+	token: Option<String>,
+}
+
+/// Renders the page verification emails link to.
+/// Verifying takes a click so link scanners in mail clients don't use up the link.
+#[component]
+pub fn VerifyEmailPage() -> impl IntoView {
+	let query = use_query::<VerifyEmailQuery>();
+	let token = move || query.with(|query| query.as_ref().ok().and_then(|query| query.token.clone()));
+
+	let (status, set_status) = create_signal("".to_string());
+	let (verified, set_verified) = create_signal(false);
+
+	let on_verify = move |_| {
+		let Some(token) = token() else {
+			return;
+		};
+
+		spawn_local(async move {
+			set_status("Verifying...".to_string());
+
+			match verify_email(token).await {
+				Ok(Ok(())) => {
+					set_status("".to_string());
+					set_verified(true);
+				},
+				Ok(Err(e)) => set_status(e),
+				Err(e) => {
+					console_log(&("Error: ".to_string() + e.to_string().as_str()));
+					set_status("Failed to verify: ".to_string() + e.to_string().as_str());
+				}
+			}
+		})
+	};
+
+	view! {
+		<Title text="Verify Email"/>
+		<div class="verify-email">
+			<h1>"Verify Email"</h1>
+			<Show
+				when=move || token().is_some()
+				fallback=|| view! { <p>"This link is missing its token. Open the link from the email again."</p> }
+			>
+				<Show
+					when=move || verified.get()
+					fallback=move || view! {
+						<p>"Confirm that this is your school email."</p>
+						<button class="login-button" on:click=on_verify>"Verify my email"</button>
+					}
+				>
+					<p>"Your school email is verified."</p>
+				</Show>
+			</Show>
+			<p>{status}</p>
+			<a href="/">"Back to listings"</a>
+		</div>
+	}
+}